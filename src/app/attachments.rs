@@ -0,0 +1,290 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use eframe::egui;
+
+use super::{methods::display_crypto_error, render::dialog_window, App};
+use crate::{file::Attachment, Attempt};
+
+/// Largest width of image previews
+const PREVIEW_WIDTH: f32 = 300.0;
+
+/// State of attachments window
+#[derive(Default)]
+pub struct AttachmentsPanel {
+    /// Whether window is open
+    open: bool,
+    /// Textures to preview attachments, by hash of data
+    ///
+    /// `None` if attachment is not an image, so it is not decoded again
+    ///
+    /// Textures of removed attachments are freed, and all are freed when window closes
+    previews: HashMap<u64, Option<egui::TextureHandle>>,
+    /// Attempt to export attachment at index without encryption, to a path
+    ///
+    /// Shows a warning dialog, before exporting
+    attempting_export: Attempt<(usize, String)>,
+}
+
+impl AttachmentsPanel {
+    /// Open window, or close if open
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Get texture to preview attachment, if it is an image
+    ///
+    /// Image is only decoded once for the same data
+    fn preview(
+        &mut self,
+        ctx: &egui::Context,
+        attachment: &Attachment,
+    ) -> Option<egui::TextureHandle> {
+        self.previews
+            .entry(attachment.hash())
+            .or_insert_with(|| {
+                load_image(&attachment.data)
+                    .map(|image| ctx.load_texture(&attachment.name, image, Default::default()))
+            })
+            .clone()
+    }
+}
+
+impl App {
+    /// Render attachments window, if open
+    ///
+    /// Files dropped onto the window are attached
+    pub(super) fn render_attachments(&mut self, ctx: &egui::Context) {
+        // Do not show attachments while locked
+        if self.locked || !self.attachments_panel.open {
+            self.attachments_panel.previews.clear();
+            self.attachments_panel.attempting_export.reset_attempt();
+            return;
+        }
+
+        let mut open = self.attachments_panel.open;
+        let mut add_paths = Vec::new();
+        let mut export = None;
+        let mut remove = None;
+        let editable = !self.file.is_read_only();
+
+        let response = egui::Window::new("Attachments")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(350.0)
+            .show(ctx, |ui| {
+                let panel = &mut self.attachments_panel;
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(editable, egui::Button::new("Add..."))
+                        .clicked()
+                    {
+                        add_paths = rfd::FileDialog::new().pick_files().unwrap_or_default();
+                    }
+                    ui.weak("or drop files here");
+                });
+
+                ui.separator();
+
+                let attachments = self.file.attachments();
+                if attachments.is_empty() {
+                    ui.weak("No attachments");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for (index, attachment) in attachments.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.strong(&attachment.name);
+                                ui.weak(display_size(attachment.data.len()));
+
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        if ui
+                                            .add_enabled(editable, egui::Button::new("Remove"))
+                                            .clicked()
+                                        {
+                                            remove = Some(index);
+                                        }
+                                        if ui.button("Export...").clicked() {
+                                            export = Some(index);
+                                        }
+                                    },
+                                );
+                            });
+
+                            if let Some(texture) = panel.preview(ctx, attachment) {
+                                let size = texture.size_vec2();
+                                let scale = (PREVIEW_WIDTH / size.x).min(1.0);
+                                ui.image(&texture, size * scale);
+                            }
+
+                            ui.separator();
+                        }
+                    });
+            });
+
+        self.attachments_panel.open = open;
+
+        // Free textures of attachments which were removed
+        let attachments = self.file.attachments();
+        self.attachments_panel.previews.retain(|hash, _| {
+            attachments
+                .iter()
+                .any(|attachment| attachment.hash() == *hash)
+        });
+
+        // Exporting attachment without encryption
+        if let Some((index, path)) = self.attachments_panel.attempting_export.action() {
+            let name = attachments
+                .get(*index)
+                .map(|attachment| attachment.name.as_str())
+                .unwrap_or_default();
+            let message = format!("{} will be saved unencrypted to {}", name, path);
+            let mut accept = false;
+            let mut cancel = false;
+
+            dialog_window("Export attachment?").show(ctx, |ui| {
+                ui.label(message);
+                ui.label("Anyone with access to this file can read it.");
+
+                ui.horizontal(|ui| {
+                    accept = ui.button("Export").clicked();
+                    cancel = ui.button("Cancel").clicked() || keys!(ui: Escape);
+                });
+            });
+
+            if accept {
+                // Override warning, and try action again
+                self.attachments_panel
+                    .attempting_export
+                    .override_condition();
+                self.call_attachment_export_action();
+            } else if cancel {
+                self.attachments_panel.attempting_export.reset_attempt();
+            }
+        }
+
+        // Attach files which were dropped onto window
+        let dropped_files = ctx.input(|input| {
+            let pointer = input.pointer.hover_pos();
+            let over_window = match (&response, pointer) {
+                (Some(response), Some(pointer)) => response.response.rect.contains(pointer),
+                // Position is not always known while files are dragged
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+
+            if over_window {
+                input.raw.dropped_files.clone()
+            } else {
+                Vec::new()
+            }
+        });
+        if editable {
+            for file in dropped_files {
+                match (file.bytes, file.path) {
+                    (Some(bytes), _) => self.file.add_attachment(&file.name, bytes.to_vec()),
+                    (None, Some(path)) => add_paths.push(path),
+                    (None, None) => (),
+                }
+            }
+        }
+
+        for path in add_paths {
+            self.add_attachment_from_path(&path);
+        }
+
+        if let Some(index) = export {
+            self.export_attachment(index);
+        }
+
+        if let Some(index) = remove {
+            self.file.remove_attachment(index);
+            // Index of attachment to export may have changed
+            self.attachments_panel.attempting_export.reset_attempt();
+        }
+    }
+
+    /// Read file, and add it as an attachment
+    fn add_attachment_from_path(&mut self, path: &Path) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match fs::read(path) {
+            Ok(data) => self.file.add_attachment(&name, data),
+            Err(error) => {
+                eprintln!("Failed to read attachment: {:?}", error);
+                self.set_error_message(display_crypto_error(cocoon::Error::Io(error)));
+            }
+        }
+    }
+
+    /// Export attachment without encryption
+    ///
+    /// Shows *save file* dialog, then a warning dialog before exporting
+    fn export_attachment(&mut self, index: usize) {
+        let Some(attachment) = self.file.attachments().get(index) else {
+            return;
+        };
+
+        if let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&attachment.name)
+            .save_file()
+            .map(|path_buf| path_buf.display().to_string())
+        {
+            self.attachments_panel
+                .attempting_export
+                .set_action((index, path));
+        }
+    }
+
+    /// Export attachment to path of attempt, if warning was accepted
+    ///
+    /// New files can only be read and written by owner, on Unix
+    fn call_attachment_export_action(&mut self) {
+        if !self
+            .attachments_panel
+            .attempting_export
+            .check_condition(false)
+        {
+            return;
+        }
+
+        if let Some((index, path)) = self.attachments_panel.attempting_export.action() {
+            if let Err(error) = self.file.export_attachment(*index, path) {
+                eprintln!("Failed to export attachment: {:?}", error);
+                self.set_error_message(display_crypto_error(cocoon::Error::Io(error)));
+            }
+        }
+        self.attachments_panel.attempting_export.reset_attempt();
+    }
+}
+
+/// Decode image from bytes
+///
+/// Returns `None` if bytes are not a supported image format
+fn load_image(bytes: &[u8]) -> Option<egui::ColorImage> {
+    let image = image::load_from_memory(bytes).ok()?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.as_flat_samples().as_slice(),
+    ))
+}
+
+/// Format number of bytes as a human-readable size
+fn display_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f32 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f32 / (1024.0 * 1024.0))
+    }
+}
@@ -0,0 +1,94 @@
+use eframe::egui;
+
+use super::{
+    diff::{diff_mode_buttons, render_diff, DiffMode},
+    methods::display_crypto_error,
+    App,
+};
+use crate::{File, KEY};
+
+/// State of window showing changes since file was saved
+#[derive(Default)]
+pub struct ChangesView {
+    /// Whether window is open
+    open: bool,
+    /// Contents of file saved on disk, or error message if it could not be read
+    saved: Option<Result<String, &'static str>>,
+    /// Whether to compare lines or words
+    mode: DiffMode,
+}
+
+impl App {
+    /// Read saved file, and show changes since it was saved
+    ///
+    /// Closes window if already open
+    pub(super) fn toggle_changes(&mut self) {
+        if self.changes_view.open {
+            self.changes_view.open = false;
+        } else {
+            self.show_changes();
+        }
+    }
+
+    /// Read saved file, and open window to show changes since it was saved
+    pub(super) fn show_changes(&mut self) {
+        let Some(path) = self.file.path() else {
+            return;
+        };
+
+        let key = self
+            .passphrase_for(Some(path), self.file.container())
+            .unwrap_or_else(|| KEY.to_string());
+
+        // File on disk is not changed, so it is only read again if requested
+        self.changes_view.saved = Some(
+            File::open_path_and_decrypt(path, &key)
+                .map(|file| file.contents().clone())
+                .map_err(display_crypto_error),
+        );
+        self.changes_view.open = true;
+    }
+
+    /// Render window of changes since file was saved, if open
+    pub(super) fn render_changes(&mut self, ctx: &egui::Context) {
+        // Do not show contents while locked
+        if self.locked {
+            return;
+        }
+
+        let mut open = self.changes_view.open;
+        let mut reload = false;
+
+        egui::Window::new("Changes since save")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                let view = &mut self.changes_view;
+
+                ui.horizontal(|ui| {
+                    diff_mode_buttons(ui, &mut view.mode);
+                    if ui.button("Reload saved file").clicked() {
+                        reload = true;
+                    }
+                });
+                ui.separator();
+
+                match &view.saved {
+                    Some(Ok(saved)) => {
+                        render_diff(ui, "changes_diff", saved, self.file.contents(), view.mode);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, *error);
+                    }
+                    None => (),
+                }
+            });
+
+        self.changes_view.open = open;
+
+        if reload {
+            self.show_changes();
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use eframe::{
+    egui::{
+        self,
+        text::{LayoutJob, TextFormat},
+        TextStyle,
+    },
+    epaint::{Color32, Stroke},
+};
+use similar::{ChangeTag, TextDiff};
+
+/// Color of text which was added
+const INSERT_COLOR: Color32 = Color32::from_rgb(0x4c, 0xaf, 0x50);
+
+/// Lines of unchanged text to show around each change, when comparing lines
+const LINE_CONTEXT: usize = 3;
+
+/// Words and spaces of unchanged text to show around each change, when comparing words
+const WORD_CONTEXT: usize = 8;
+
+/// Whether to compare text by lines or words
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum DiffMode {
+    #[default]
+    Lines,
+    Words,
+}
+
+/// Show buttons to choose diff mode
+pub fn diff_mode_buttons(ui: &mut egui::Ui, mode: &mut DiffMode) {
+    ui.selectable_value(mode, DiffMode::Lines, "Lines");
+    ui.selectable_value(mode, DiffMode::Words, "Words");
+}
+
+/// Changes between old and new text, with some unchanged text around them
+///
+/// Can be kept between frames, as comparing is slow for large texts
+pub struct Diff {
+    /// Whether lines or words were compared
+    mode: DiffMode,
+    /// Groups of changes which are near each other, with their text
+    groups: Vec<Vec<(ChangeTag, String)>>,
+}
+
+impl Diff {
+    /// Compare old and new text
+    pub fn new(old: &str, new: &str, mode: DiffMode) -> Self {
+        let diff = match mode {
+            DiffMode::Lines => TextDiff::from_lines(old, new),
+            DiffMode::Words => TextDiff::from_words(old, new),
+        };
+        let groups = diff
+            .grouped_ops(match mode {
+                DiffMode::Lines => LINE_CONTEXT,
+                DiffMode::Words => WORD_CONTEXT,
+            })
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .flat_map(|op| diff.iter_changes(op))
+                    .map(|change| (change.tag(), change.value().to_string()))
+                    .collect()
+            })
+            .collect();
+
+        Self { mode, groups }
+    }
+
+    /// Show changes
+    ///
+    /// `id_source` must be unique for each diff shown at once
+    pub fn show(&self, ui: &mut egui::Ui, id_source: &str) {
+        if self.groups.is_empty() {
+            ui.weak("No changes");
+            return;
+        }
+
+        egui::ScrollArea::both()
+            .id_source(id_source)
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for (index, group) in self.groups.iter().enumerate() {
+                    if index > 0 {
+                        ui.separator();
+                    }
+
+                    match self.mode {
+                        // Each line with a sign
+                        DiffMode::Lines => {
+                            for (tag, value) in group {
+                                let (sign, color) = match tag {
+                                    ChangeTag::Delete => ("-", ui.visuals().error_fg_color),
+                                    ChangeTag::Insert => ("+", INSERT_COLOR),
+                                    ChangeTag::Equal => (" ", ui.visuals().weak_text_color()),
+                                };
+                                let line = value.trim_end_matches(['\r', '\n']);
+
+                                ui.add(
+                                    egui::Label::new(
+                                        egui::RichText::new(format!("{} {}", sign, line))
+                                            .monospace()
+                                            .color(color),
+                                    )
+                                    .wrap(false),
+                                );
+                            }
+                        }
+
+                        // Inline text, with removed words struck through
+                        DiffMode::Words => {
+                            let format = TextFormat::simple(
+                                TextStyle::Monospace.resolve(ui.style()),
+                                ui.visuals().weak_text_color(),
+                            );
+
+                            let mut job = LayoutJob::default();
+                            for (tag, value) in group {
+                                let format = match tag {
+                                    ChangeTag::Delete => {
+                                        let color = ui.visuals().error_fg_color;
+                                        TextFormat {
+                                            color,
+                                            strikethrough: Stroke::new(1.0, color),
+                                            ..format.clone()
+                                        }
+                                    }
+                                    ChangeTag::Insert => TextFormat {
+                                        color: INSERT_COLOR,
+                                        underline: Stroke::new(1.0, INSERT_COLOR),
+                                        ..format.clone()
+                                    },
+                                    ChangeTag::Equal => format.clone(),
+                                };
+                                job.append(value, 0.0, format);
+                            }
+
+                            ui.label(job);
+                        }
+                    }
+                }
+            });
+    }
+}
+
+/// Show changes between old and new text, with some unchanged text around them
+///
+/// `id_source` must be unique for each diff shown at once
+pub fn render_diff(ui: &mut egui::Ui, id_source: &str, old: &str, new: &str, mode: DiffMode) {
+    Diff::new(old, new, mode).show(ui, id_source);
+}
@@ -0,0 +1,259 @@
+use std::{cell::Cell, ops::Range};
+
+use eframe::{
+    egui::{
+        self,
+        text::{CCursor, CCursorRange, LayoutJob, TextFormat},
+        text_edit::TextEditOutput,
+        Align, Event, Key, TextEdit, TextStyle,
+    },
+    emath::Align2,
+    epaint::{Color32, FontId},
+};
+
+use super::App;
+use crate::highlight::highlight;
+
+/// Space on each side of line numbers
+const GUTTER_MARGIN: f32 = 6.0;
+
+/// Id of editor text edit, to change its cursor
+const EDITOR_ID: &str = "editor";
+
+impl App {
+    /// Render editable text of file contents, with line numbers and syntax highlighting
+    ///
+    /// Highlights matches of find bar, and selects current match if requested
+    pub(super) fn render_editor(&mut self, ui: &mut egui::Ui) {
+        // Taken from find bar while text is laid out, as text edit borrows app
+        let mut search_cache = self.find_bar.take_cache();
+        let current_match = self.find_bar.current();
+        // Range of current match, in text which was laid out
+        let current_range = Cell::new(None);
+
+        // Lay out text, wrapping lines only if enabled in settings
+        let wrap = self.settings.wrap;
+        let language = self.file.language();
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let format = TextFormat::simple(
+                TextStyle::Monospace.resolve(ui.style()),
+                ui.visuals().widgets.inactive.text_color(),
+            );
+
+            let mut job = LayoutJob::default();
+            job.wrap.max_width = if wrap { wrap_width } else { f32::INFINITY };
+
+            let highlights = highlight(ui.ctx(), text, language);
+            // Matches are only found again if text or query changed
+            let matches = match &mut search_cache {
+                Some(cache) => {
+                    cache.find(text);
+                    cache.matches()
+                }
+                None => &[],
+            };
+            current_range.set(matches.get(current_match).cloned());
+
+            // Empty text must still have a section, for height of cursor
+            if text.is_empty() {
+                job.append("", 0.0, format.clone());
+            }
+
+            // Split text into sections, wherever color or match background changes
+            let mut bounds: Vec<usize> = highlights
+                .iter()
+                .map(|(range, _)| range)
+                .chain(matches)
+                .flat_map(|range| [range.start, range.end])
+                .chain([0, text.len()])
+                .collect();
+            bounds.sort_unstable();
+            bounds.dedup();
+
+            // Spans and matches are both in order, so only move forwards through each
+            let mut highlight_index = 0;
+            let mut match_index = 0;
+            for section in bounds.windows(2) {
+                let (start, end) = (section[0], section[1]);
+
+                while highlights
+                    .get(highlight_index)
+                    .is_some_and(|(range, _)| range.end <= start)
+                {
+                    highlight_index += 1;
+                }
+                while matches
+                    .get(match_index)
+                    .is_some_and(|range| range.end <= start)
+                {
+                    match_index += 1;
+                }
+
+                let color = highlights
+                    .get(highlight_index)
+                    .filter(|(range, _)| range.start <= start)
+                    .map_or(format.color, |(_, color)| *color);
+                let background = matches
+                    .get(match_index)
+                    .filter(|range| range.start <= start)
+                    .map_or(format.background, |_| {
+                        match_color(ui, match_index == current_match)
+                    });
+
+                job.append(
+                    &text[start..end],
+                    0.0,
+                    TextFormat {
+                        color,
+                        background,
+                        ..format.clone()
+                    },
+                );
+            }
+
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+
+        // Width of widest line number
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let digits = self.file.contents().split('\n').count().to_string().len();
+        let gutter_width = ui.fonts(|fonts| fonts.glyph_width(&font_id, '0')) * digits as f32
+            + GUTTER_MARGIN * 2.0;
+
+        // Text edit has its own undo, which does not know about edits from find and replace,
+        //      and is reset when file changes
+        // Undo and redo commands are used instead, but are not run if there is nothing to undo or redo
+        // Text edit undoes with Ctrl+Z, and redoes with Ctrl+Shift+Z or Ctrl+Y
+        ui.input_mut(|input| {
+            input.events.retain(|event| {
+                !matches!(
+                    event,
+                    Event::Key { key: Key::Z | Key::Y, modifiers, .. } if modifiers.command
+                )
+            })
+        });
+
+        let read_only = self.file.is_read_only();
+
+        let output = egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    // Space for line numbers, which are painted after text is laid out
+                    let (gutter, _) =
+                        ui.allocate_exact_size(egui::vec2(gutter_width, 0.0), egui::Sense::hover());
+
+                    let output = TextEdit::multiline(self.file.contents_mut())
+                        .id(egui::Id::new(EDITOR_ID))
+                        .interactive(!read_only)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .min_size(ui.available_size())
+                        .layouter(&mut layouter)
+                        .show(ui);
+
+                    paint_line_numbers(ui, &output, gutter.right(), font_id);
+
+                    // Set save state to unsaved if text was changed
+                    if output.response.changed() {
+                        self.register_edit();
+                    }
+
+                    // Remember cursor for status bar
+                    // Repaint if it changed, as status bar was already rendered this frame
+                    let cursor = output
+                        .cursor_range
+                        .map(|cursor_range| cursor_range.as_ccursor_range());
+                    if cursor != self.cursor {
+                        self.cursor = cursor;
+                        ui.ctx().request_repaint();
+                    }
+
+                    if self.find_bar.take_jump() {
+                        if let Some(range) = current_range.take() {
+                            self.jump_to_match(ui, output, range);
+                        }
+                    }
+                });
+            });
+
+        self.find_bar.restore_cache(search_cache);
+
+        // Remember scroll position, for Markdown preview
+        let max_offset = output.content_size.y - output.inner_rect.height();
+        self.editor_scroll = if max_offset > 0.0 {
+            (output.state.offset.y / max_offset).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// Select byte range of file contents in editor, such as text restored by undo
+    pub(super) fn select_in_editor(&self, ctx: &egui::Context, range: Range<usize>) {
+        let contents = self.file.contents();
+        let start = CCursor::new(contents[..range.start].chars().count());
+        let end = CCursor::new(contents[..range.end].chars().count());
+
+        let id = egui::Id::new(EDITOR_ID);
+        let mut state = TextEdit::load_state(ctx, id).unwrap_or_default();
+        state.set_ccursor_range(Some(CCursorRange::two(start, end)));
+        state.store(ctx, id);
+    }
+
+    /// Select match of find bar, at byte range of file contents, and scroll to it
+    fn jump_to_match(&self, ui: &egui::Ui, output: TextEditOutput, range: Range<usize>) {
+        let contents = self.file.contents();
+
+        let start = CCursor::new(contents[..range.start].chars().count());
+        let end = CCursor::new(contents[..range.end].chars().count());
+
+        let mut state = output.state;
+        state.set_ccursor_range(Some(CCursorRange::two(start, end)));
+        state.store(ui.ctx(), output.response.id);
+
+        let rect = output
+            .galley
+            .pos_from_cursor(&output.galley.from_ccursor(start))
+            .translate(output.text_draw_pos.to_vec2());
+        ui.scroll_to_rect(rect, Some(Align::Center));
+    }
+}
+
+/// Paint line number beside first row of each line
+///
+/// Lines may have multiple rows if wrapped
+fn paint_line_numbers(ui: &egui::Ui, output: &TextEditOutput, right: f32, font_id: FontId) {
+    let color = ui.visuals().weak_text_color();
+    let visible = ui.clip_rect();
+    let mut line = 1;
+    let mut is_line_start = true;
+
+    for row in &output.galley.rows {
+        if is_line_start {
+            let top = output.text_draw_pos.y + row.rect.top();
+
+            // Skip rows which are scrolled out of view
+            if top < visible.bottom() && top + row.rect.height() > visible.top() {
+                ui.painter().text(
+                    egui::pos2(right - GUTTER_MARGIN, top),
+                    Align2::RIGHT_TOP,
+                    line,
+                    font_id.clone(),
+                    color,
+                );
+            }
+            line += 1;
+        }
+        is_line_start = row.ends_with_newline;
+    }
+}
+
+/// Background color of a find match
+fn match_color(ui: &egui::Ui, is_current: bool) -> Color32 {
+    let color = ui.visuals().selection.bg_fill;
+    if is_current {
+        color
+    } else {
+        color.linear_multiply(0.4)
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    mem,
+    ops::Range,
+};
+
+use eframe::egui::{self, Key};
+
+use super::App;
+use crate::search::{Search, SearchOptions};
+
+/// State of find and replace bar
+#[derive(Default)]
+pub struct FindBar {
+    /// Whether bar is open
+    open: bool,
+    /// Whether replace field is shown
+    replace: bool,
+
+    /// Text to find
+    query: String,
+    /// Text to replace matches with
+    replacement: String,
+    /// Search options, such as case sensitivity
+    options: SearchOptions,
+
+    /// Index of current match
+    current: usize,
+    /// Whether editor should select and scroll to current match on next frame
+    jump: bool,
+    /// Whether find field should be focused on next frame
+    focus: bool,
+
+    /// Compiled query, and its matches
+    cache: SearchCache,
+}
+
+/// Compiled query, and its matches in text
+///
+/// Kept between frames, as compiling and finding every frame is slow for large files
+#[derive(Default)]
+pub struct SearchCache {
+    /// Query which search was compiled from
+    query: String,
+    /// Options which search was compiled with
+    options: SearchOptions,
+    /// Compiled query
+    ///
+    /// `None` if query is empty, or error if it is an invalid regular expression
+    search: Option<Result<Search, regex::Error>>,
+    /// Hash of text which matches were found in, and byte ranges of matches
+    ///
+    /// `None` if matches were not found since query was compiled
+    matches: Option<(u64, Vec<Range<usize>>)>,
+}
+
+impl SearchCache {
+    /// Compile query again, if query or options changed
+    fn compile(&mut self, query: &str, options: SearchOptions) {
+        if self.query == query && self.options == options {
+            return;
+        }
+
+        self.query = query.to_string();
+        self.options = options;
+        self.search = Search::new(query, options);
+        self.matches = None;
+    }
+
+    /// Get compiled query
+    ///
+    /// `None` if query is empty, or error if it is an invalid regular expression
+    fn search(&self) -> Option<&Result<Search, regex::Error>> {
+        self.search.as_ref()
+    }
+
+    /// Find matches in text, unless they were already found in the same text
+    pub fn find(&mut self, text: &str) {
+        let Some(Ok(search)) = &self.search else {
+            return;
+        };
+
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if !matches!(&self.matches, Some((found_in, _)) if *found_in == hash) {
+            self.matches = Some((hash, search.find_all(text)));
+        }
+    }
+
+    /// Get byte ranges of matches, found by last `find`
+    ///
+    /// Empty if query is empty or invalid
+    pub fn matches(&self) -> &[Range<usize>] {
+        match (&self.search, &self.matches) {
+            (Some(Ok(_)), Some((_, matches))) => matches,
+            _ => &[],
+        }
+    }
+}
+
+impl FindBar {
+    /// Open bar, and focus find field
+    ///
+    /// Shows replace field if `replace` is `true`
+    pub fn open(&mut self, replace: bool) {
+        self.open = true;
+        self.replace = replace;
+        self.focus = true;
+    }
+
+    /// Take compiled query and its matches, if bar is open
+    ///
+    /// Query is compiled again only if it changed
+    ///
+    /// Must be given back with `restore_cache`, so it is kept for next frame
+    pub fn take_cache(&mut self) -> Option<SearchCache> {
+        if !self.open {
+            return None;
+        }
+        self.cache.compile(&self.query, self.options);
+        Some(mem::take(&mut self.cache))
+    }
+
+    /// Give back cache from `take_cache`
+    pub fn restore_cache(&mut self, cache: Option<SearchCache>) {
+        if let Some(cache) = cache {
+            self.cache = cache;
+        }
+    }
+
+    /// Index of current match
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Returns `true` once if editor should select and scroll to current match
+    pub fn take_jump(&mut self) -> bool {
+        std::mem::take(&mut self.jump)
+    }
+
+    /// Move to next or previous match, wrapping around
+    fn step(&mut self, count: usize, forwards: bool) {
+        if count == 0 {
+            return;
+        }
+        self.current = if forwards {
+            (self.current + 1) % count
+        } else {
+            (self.current + count - 1) % count
+        };
+        self.jump = true;
+    }
+}
+
+impl App {
+    /// Render find and replace bar, if open
+    pub(super) fn render_find_bar(&mut self, ui: &mut egui::Ui) {
+        let Some(mut cache) = self.find_bar.take_cache() else {
+            return;
+        };
+        cache.find(self.file.contents());
+        self.render_find_bar_with(ui, &cache);
+        self.find_bar.restore_cache(Some(cache));
+    }
+
+    /// Render find and replace bar, with compiled query and its matches
+    fn render_find_bar_with(&mut self, ui: &mut egui::Ui, cache: &SearchCache) {
+        let search = cache.search();
+        let matches = cache.matches();
+
+        // Text may have changed since last frame
+        if self.find_bar.current >= matches.len() {
+            self.find_bar.current = 0;
+        }
+
+        // * Find
+
+        ui.horizontal(|ui| {
+            let bar = &mut self.find_bar;
+
+            let find = ui.add(
+                egui::TextEdit::singleline(&mut bar.query)
+                    .hint_text("Find")
+                    .desired_width(200.0),
+            );
+            if std::mem::take(&mut bar.focus) {
+                find.request_focus();
+            }
+            if find.changed() {
+                bar.current = 0;
+                bar.jump = true;
+            }
+
+            // Enter for next match, Shift+Enter for previous match
+            // Text edit loses focus on enter, so focus is requested again
+            if find.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+                let backwards = ui.input(|input| input.modifiers.shift);
+                bar.step(matches.len(), !backwards);
+                find.request_focus();
+            }
+            // Text edit also loses focus on escape
+            if find.lost_focus() && ui.input(|input| input.key_pressed(Key::Escape)) {
+                bar.open = false;
+            }
+
+            // Options
+            let options = bar.options;
+            ui.toggle_value(&mut bar.options.case_sensitive, "Aa")
+                .on_hover_text("Match case");
+            ui.toggle_value(&mut bar.options.whole_word, "W")
+                .on_hover_text("Match whole word");
+            ui.toggle_value(&mut bar.options.regex, ".*")
+                .on_hover_text("Use regular expression");
+            if bar.options != options {
+                bar.current = 0;
+                bar.jump = true;
+            }
+
+            // Match count, or error
+            match search {
+                Some(Err(_)) => {
+                    ui.colored_label(ui.visuals().error_fg_color, "Invalid regex");
+                }
+                Some(Ok(_)) if matches.is_empty() => {
+                    ui.label("No matches");
+                }
+                Some(Ok(_)) => {
+                    ui.label(format!("{} of {}", bar.current + 1, matches.len()));
+                }
+                None => (),
+            }
+
+            if ui
+                .add_enabled(!matches.is_empty(), egui::Button::new("⏶"))
+                .on_hover_text("Previous match (Shift+Enter)")
+                .clicked()
+            {
+                bar.step(matches.len(), false);
+            }
+            if ui
+                .add_enabled(!matches.is_empty(), egui::Button::new("⏷"))
+                .on_hover_text("Next match (Enter)")
+                .clicked()
+            {
+                bar.step(matches.len(), true);
+            }
+
+            if ui.button("Replace...").clicked() {
+                bar.replace = !bar.replace;
+            }
+            if ui.button("✖").on_hover_text("Close").clicked() {
+                bar.open = false;
+            }
+        });
+
+        // * Replace
+
+        // Read-only files cannot be edited
+        if !self.find_bar.replace || self.file.is_read_only() {
+            return;
+        }
+
+        let mut replaced = None;
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.find_bar.replacement)
+                    .hint_text("Replace")
+                    .desired_width(200.0),
+            );
+
+            // Field is still shown, so replacement can be typed before query
+            let Some(Ok(search)) = search else {
+                return;
+            };
+            let replacement = &self.find_bar.replacement;
+
+            if ui
+                .add_enabled(!matches.is_empty(), egui::Button::new("Replace"))
+                .clicked()
+            {
+                // Replace current match, and stay at same index, which is now the next match
+                replaced = matches.get(self.find_bar.current).and_then(|range| {
+                    search.replace_at(self.file.contents(), range.clone(), replacement)
+                });
+                self.find_bar.jump = true;
+            }
+
+            if ui
+                .add_enabled(!matches.is_empty(), egui::Button::new("Replace all"))
+                .clicked()
+            {
+                let (text, count) = search.replace_all(self.file.contents(), replacement);
+                println!("Replaced {} matches", count);
+                replaced = Some(text);
+            }
+        });
+
+        // Replace contents in a single change, so it can be undone in one step
+        if let Some(text) = replaced {
+            *self.file.contents_mut() = text;
+            self.register_edit();
+        }
+    }
+}
@@ -1,9 +1,17 @@
-use std::{io, thread};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 
 use eframe::egui;
 
-use super::{App, CloseFileAction, ConcurrentMessage};
-use crate::{file_dialog, File, KEY};
+use super::{passphrase::PassphraseAction, App, CloseFileAction, ConcurrentMessage};
+use crate::{
+    file::{remove_securely, Container, FileLock, LockOwner, ReadOnly},
+    file_dialog, Command, File, KEY,
+};
 
 impl App {
     // * Error messages
@@ -11,7 +19,7 @@ impl App {
     /// Set error message
     ///
     /// This function can only be used in main thread
-    fn set_error_message(&mut self, message: &'static str) {
+    pub(super) fn set_error_message(&mut self, message: &'static str) {
         *self.error_message.lock().unwrap() = Some(message);
     }
 
@@ -27,6 +35,125 @@ impl App {
         *self.error_message.lock().unwrap() = None;
     }
 
+    // * Commands
+
+    /// Returns `true` if command is allowed to run
+    ///
+    /// Commands which use the file are disabled while file is writing on another thread
+    ///
+    /// Commands which use the file are also disabled while editor is locked
+    pub(super) fn command_enabled(&self, command: Command) -> bool {
+        let concurrently_writing = *self.writing.lock().unwrap();
+
+        match command {
+            Command::Save => {
+                !self.file.is_registered_and_saved()
+                    && !self.file.is_read_only()
+                    && !concurrently_writing
+                    && !self.locked
+            }
+            Command::SaveAs
+            | Command::SaveAsAge
+            | Command::Open
+            | Command::Import
+            | Command::PasteArmored => !concurrently_writing && !self.locked,
+            Command::Export | Command::CopyArmored => !self.locked,
+            Command::New => {
+                !self.file.is_unregistered_and_unchanged() && !concurrently_writing && !self.locked
+            }
+            Command::ShowChanges => {
+                self.file.path().is_some() && !self.locked && !concurrently_writing
+            }
+            Command::ReadOnly => {
+                !self.file.read_only().is_some_and(ReadOnly::is_forced) && !self.locked
+            }
+            Command::Undo => {
+                self.file.undo_history().can_undo() && !self.file.is_read_only() && !self.locked
+            }
+            Command::Redo => {
+                self.file.undo_history().can_redo() && !self.file.is_read_only() && !self.locked
+            }
+            Command::Replace => !self.file.is_read_only() && !self.locked,
+            Command::UndoHistory
+            | Command::Find
+            | Command::Preview
+            | Command::Versions
+            | Command::Attachments
+            | Command::Properties
+            | Command::Migrate
+            | Command::Lock => !self.locked,
+            Command::Preferences | Command::Palette => true,
+        }
+    }
+
+    /// Run command, if it is allowed to run
+    pub(super) fn run_command(&mut self, command: Command, ctx: &egui::Context) {
+        if !self.command_enabled(command) {
+            return;
+        }
+
+        match command {
+            Command::Save => self.file_save_or_save_as(ctx),
+            Command::SaveAs => self.file_save_as(ctx),
+            Command::SaveAsAge => self.save_as_age_dialog = true,
+            Command::Open => self.file_open(),
+            Command::New => self.file_new(),
+            Command::Import => self.file_import(),
+            Command::Export => self.file_export(),
+            Command::CopyArmored => self.copy_armored(ctx),
+            Command::PasteArmored => self.paste_armored(),
+            Command::Migrate => self.migration_wizard.open(None),
+            Command::ShowChanges => self.toggle_changes(),
+            Command::ReadOnly => {
+                let read_only = match self.file.read_only() {
+                    Some(_) => None,
+                    None => Some(ReadOnly::User),
+                };
+                self.file.set_read_only(read_only);
+            }
+            Command::Undo => {
+                if let Some(range) = self.file.undo() {
+                    self.select_in_editor(ctx, range);
+                }
+            }
+            Command::Redo => {
+                if let Some(range) = self.file.redo() {
+                    self.select_in_editor(ctx, range);
+                }
+            }
+            Command::UndoHistory => self.show_undo_history = !self.show_undo_history,
+            Command::Find => self.find_bar.open(false),
+            Command::Replace => self.find_bar.open(true),
+            Command::Preview => self.show_preview = !self.show_preview,
+            Command::Versions => self.versions_panel.toggle(),
+            Command::Attachments => self.attachments_panel.toggle(),
+            Command::Properties => self.show_properties = !self.show_properties,
+            Command::Lock => self.locked = true,
+            Command::Preferences => self.show_preferences = true,
+            Command::Palette => self.command_palette.toggle(),
+        }
+    }
+
+    /// Run any command whose keybind was pressed
+    ///
+    /// Disabled while a new keybind is being captured
+    pub(super) fn run_keybinds(&mut self, ctx: &egui::Context) {
+        if self.capturing_keybind.is_some() {
+            return;
+        }
+
+        for &command in Command::ALL {
+            let Some(keybind) = self.settings.keybinds.get(command) else {
+                continue;
+            };
+
+            // Keybind is only consumed if command is enabled
+            if self.command_enabled(command) && ctx.input_mut(|i| i.consume_shortcut(&keybind)) {
+                self.run_command(command, ctx);
+            }
+        }
+    }
+
     // * Save file (save, save as)
 
     /// Save existing file, or save as if not registered
@@ -50,13 +177,52 @@ impl App {
     ///
     /// Shows *save file* dialog
     pub(super) fn file_save_as(&mut self, ctx: &egui::Context) {
+        let extension = self.settings.file_extension.clone();
+        self.file_save_as_with_extension(ctx, &extension);
+    }
+
+    /// Save file as age file, with passphrase or X25519 identity
+    ///
+    /// Shows *save file* dialog
+    pub(super) fn file_save_as_age(&mut self, ctx: &egui::Context, container: Container) {
+        self.file.set_container(container);
+        self.file.set_armored(false);
+        self.file_save_as_with_extension(ctx, "age");
+    }
+
+    /// Save file as, with extension for dialog filter and paths without extension
+    fn file_save_as_with_extension(&mut self, ctx: &egui::Context, extension: &str) {
         println!("Save as");
 
-        if let Some(path) = file_dialog()
-            .save_file()
-            .map(|path_buf| path_buf.display().to_string())
-        {
+        if let Some(path) = self.file_dialog(extension).save_file().map(|path_buf| {
+            with_file_extension(path_buf, extension)
+                .display()
+                .to_string()
+        }) {
+            // Lock new path, keeping lock of old path until save finishes
+            if self
+                .file_lock
+                .as_ref()
+                .is_some_and(|lock| lock.is_for(&path))
+            {
+                // Saving back to path which is already locked
+                self.pending_lock = None;
+            } else if Some(&path) != self.file.path() {
+                match self.lock_path(&path) {
+                    Ok(lock) => self.pending_lock = Some((path.clone(), lock)),
+                    Err(owner) => {
+                        eprintln!("File is locked by {}", owner);
+                        self.set_error_message(
+                            "File is being edited by another program. Choose a different file",
+                        );
+                        return;
+                    }
+                }
+            }
+
+            // Copy of file is no longer read-only
             self.file.set_path(&path);
+            self.file.set_read_only(None);
 
             self.file_save_existing(&path, ctx);
         };
@@ -64,10 +230,36 @@ impl App {
 
     /// Save existing file in new thread (concurrent / synchronous)
     ///
+    /// Does not save if file was changed by another program, and shows a dialog instead
+    ///
+    /// Asks for passphrase first, if format uses one and it was not entered for path
+    ///
     /// Should not be ran, unless file is already registered
-    fn file_save_existing(&mut self, path: &str, ctx: &egui::Context) {
+    pub(super) fn file_save_existing(&mut self, path: &str, ctx: &egui::Context) {
         println!("Save existing");
 
+        if self.file.is_read_only() {
+            return;
+        }
+
+        // Format cannot save everything in file
+        if self.file.has_data_not_in_format() {
+            self.set_error_message(TEXT_ONLY_ERROR);
+            return;
+        }
+
+        let Some(key) = self.passphrase_for(Some(&path.to_string()), self.file.container()) else {
+            self.ask_passphrase(PassphraseAction::Save(path.to_string()), false);
+            return;
+        };
+
+        // Changes by other program would be lost
+        if self.file.is_changed_on_disk() {
+            println!("File changed on disk, not saving");
+            self.external_change = true;
+            return;
+        }
+
         // Set as writing
         *self.writing.lock().unwrap() = true;
         // Request to draw a new frame to update writing status
@@ -94,13 +286,14 @@ impl App {
         thread::spawn(move || {
             // Save file and Handle errors
             // This can be a slow process (especially in debug build), hence the concurrent thread
-            match file.save_to_path_encrypted(&path, KEY) {
+            match file.save_to_path_encrypted(&path, &key) {
                 // Successful save
                 Ok(()) => {
                     // Send a message to main thread, to update value of save status
                     // This will be recieved on the next frame (requested above)
+                    // Saved file is sent back, as it may have a new version
                     sender
-                        .send(ConcurrentMessage::FinishConcurrentSave)
+                        .send(ConcurrentMessage::FinishConcurrentSave(Box::new(file)))
                         .expect("Send message")
                 }
 
@@ -138,29 +331,173 @@ impl App {
             return;
         }
 
-        if let Some(path) = file_dialog()
+        if let Some(path) = self
+            .file_dialog(&self.settings.file_extension)
+            .add_filter("age file", &["age"])
+            .add_filter("OpenPGP message", &["gpg", "asc"])
             .pick_file()
             .map(|path_buf| path_buf.display().to_string())
         {
-            // Same file is already open
-            // Don't open again
-            if Some(&path) == self.file.path() {
-                return;
-            }
+            self.open_path(path);
+        };
+    }
+
+    /// Open file from given path, without showing dialog
+    ///
+    /// Attempts to close current file (See `self.attempt_file_close`)
+    pub(super) fn file_open_path(&mut self, path: String) {
+        println!("Open path");
 
-            // This can be a slow process (especially in debug build), but should not use concurrent thread,
-            //      as no user actions can be performed until file loads anyway
-            match File::open_path_and_decrypt(path, KEY) {
-                // Successful read
-                Ok(file) => {
+        if !self.file_can_close() {
+            self.attempting_file_close
+                .set_action(CloseFileAction::OpenPath(path));
+            return;
+        }
+
+        self.open_path(path);
+    }
+
+    /// Read and decrypt file, replacing current file
+    ///
+    /// Files with a passphrase are opened with the passphrase entered for path,
+    ///     or the legacy key, as older versions saved them with it
+    /// If this fails, passphrase is asked for
+    ///
+    /// Does not check if current file can close
+    pub(super) fn open_path(&mut self, path: String) {
+        // Same file is already open
+        // Don't open again
+        if Some(&path) == self.file.path() {
+            return;
+        }
+
+        let asks_passphrase = File::detect_container(&path).is_ok_and(Container::asks_passphrase);
+        let entered = self.passphrases.get(&path).cloned();
+        let key = entered
+            .as_deref()
+            .filter(|_| asks_passphrase)
+            .unwrap_or(KEY);
+
+        // This can be a slow process (especially in debug build), but should not use concurrent thread,
+        //      as no user actions can be performed until file loads anyway
+        let opened = File::open_path_and_decrypt(&path, key);
+        if opened.is_ok() && asks_passphrase {
+            self.passphrases.insert(path.clone(), key.to_string());
+        }
+
+        match opened {
+            // Successful read
+            Ok(file) => match self.lock_path(&path) {
+                Ok(lock) => {
                     self.file = file;
+                    self.file_lock = lock;
+                    self.pending_lock = None;
+                    self.pending_import = None;
+                    self.recent_files.add(&path);
+                    self.check_permissions();
+                    self.check_legacy_key();
                 }
 
-                // An error occurred
-                // Display a readable  error on UI
-                Err(error) => self.set_error_message(display_crypto_error(error)),
+                // File is being edited by another program
+                // Ask to open read-only
+                Err(owner) => self.lock_conflict = Some((file, owner)),
+            },
+
+            // Passphrase is wrong, or was not entered yet
+            Err(cocoon::Error::Cryptography) if asks_passphrase => {
+                self.passphrases.remove(&path);
+                self.ask_passphrase(PassphraseAction::Open(path), entered.is_some());
             }
-        };
+
+            // An error occurred
+            // Display a readable  error on UI
+            Err(error) => {
+                // Forget file if it no longer exists
+                if let cocoon::Error::Io(error) = &error {
+                    if error.kind() == io::ErrorKind::NotFound {
+                        self.recent_files.remove(&path);
+                    }
+                }
+
+                self.set_error_message(display_crypto_error(error))
+            }
+        }
+    }
+
+    /// Lock file at path, so it is not edited by another program
+    ///
+    /// Returns owner of lock, if file is locked by another program
+    ///
+    /// Returns `None` if lock file could not be written, such as in a read-only directory
+    fn lock_path(&self, path: &str) -> Result<Option<FileLock>, LockOwner> {
+        match FileLock::acquire(path) {
+            Ok(Ok(lock)) => Ok(Some(lock)),
+            Ok(Err(owner)) => Err(owner),
+            Err(error) => {
+                eprintln!("Failed to lock file: {:?}", error);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Open file which is locked by another program, without editing
+    pub(super) fn open_read_only(&mut self, mut file: File) {
+        file.set_read_only(Some(ReadOnly::Locked));
+        if let Some(path) = file.path() {
+            self.recent_files.add(path);
+        }
+
+        self.file = file;
+        self.file_lock = None;
+        self.pending_lock = None;
+        self.pending_import = None;
+        self.check_permissions();
+        self.check_legacy_key();
+    }
+
+    /// Show warning if file can be read or written by other users
+    fn check_permissions(&mut self) {
+        self.insecure_permissions = self.file.has_insecure_permissions();
+    }
+
+    /// Suggest migrating file, if it was opened with the legacy key
+    fn check_legacy_key(&mut self) {
+        let container = self.file.container();
+        self.legacy_key_nudge = container.uses_password()
+            && self.passphrase_for(self.file.path(), container).as_deref() == Some(KEY);
+    }
+
+    /// Open migration wizard, for directory of open file
+    pub(super) fn migrate_open_file(&mut self) {
+        self.legacy_key_nudge = false;
+
+        let directory = self
+            .file
+            .path()
+            .and_then(|path| Path::new(path).parent())
+            .map(|directory| directory.display().to_string());
+        self.migration_wizard.open(directory);
+    }
+
+    /// Remove permissions of other users from file
+    pub(super) fn fix_permissions(&mut self) {
+        self.insecure_permissions = false;
+
+        if let Err(error) = self.file.fix_permissions() {
+            self.set_error_message(display_crypto_error(cocoon::Error::Io(error)));
+        }
+    }
+
+    /// Create file open/save dialog, filtering files by extension
+    ///
+    /// Starts in directory of most recent file, or default directory from settings
+    fn file_dialog(&self, extension: &str) -> rfd::FileDialog {
+        let start_dir = self
+            .recent_files
+            .last_dir()
+            .or_else(|| self.settings.default_dir());
+
+        file_dialog(start_dir, extension)
     }
 
     // * New file
@@ -182,6 +519,239 @@ impl App {
         println!("New file");
 
         self.file = File::default();
+        self.file.set_cipher(self.settings.default_cipher);
+        self.file_lock = None;
+        self.pending_lock = None;
+        self.pending_import = None;
+    }
+
+    // * Settings, autosave, and lock
+
+    /// Apply theme and font size from settings, if changed
+    pub(super) fn apply_settings(&self, ctx: &egui::Context) {
+        let current = ctx.style();
+        let style = self.settings.style(&current);
+
+        if style != *current {
+            ctx.set_style(style);
+        }
+    }
+
+    /// Register that file contents were edited, for undo history and autosave
+    ///
+    /// Does nothing if file is read-only, as contents cannot be edited
+    pub(super) fn register_edit(&mut self) {
+        if self.file.is_read_only() {
+            return;
+        }
+
+        self.file.record_edit();
+        self.file.mark_as_unsaved();
+        self.last_edit = Some(Instant::now());
+    }
+
+    /// Save file if autosave is enabled, and file has not been edited recently
+    ///
+    /// Only saves registered files, as saving unregistered files would show a dialog
+    pub(super) fn autosave(&mut self, ctx: &egui::Context) {
+        if !self.settings.autosave || self.file.is_read_only() || *self.writing.lock().unwrap() {
+            return;
+        }
+
+        let Some(last_edit) = self.last_edit else {
+            return;
+        };
+        let Some(path) = self.file.path().cloned() else {
+            return;
+        };
+        if !self.file.is_changed() {
+            return;
+        }
+
+        let delay = Duration::from_secs(self.settings.autosave_delay);
+        let elapsed = last_edit.elapsed();
+
+        if elapsed >= delay {
+            println!("Autosave");
+            self.last_edit = None;
+            self.file_save_existing(&path, ctx);
+        } else {
+            // Check again when delay has passed, even without user input
+            ctx.request_repaint_after(delay - elapsed);
+        }
+    }
+
+    /// Lock editor if there has been no user input for the lock timeout
+    pub(super) fn check_lock_timeout(&mut self, ctx: &egui::Context) {
+        if ctx.input(|input| !input.events.is_empty()) {
+            self.last_activity = Instant::now();
+        }
+
+        if self.locked || self.settings.lock_timeout == 0 {
+            return;
+        }
+
+        let timeout = Duration::from_secs(self.settings.lock_timeout * 60);
+        let elapsed = self.last_activity.elapsed();
+
+        if elapsed >= timeout {
+            println!("Lock");
+            self.locked = true;
+        } else {
+            // Check again when timeout has passed, even without user input
+            ctx.request_repaint_after(timeout - elapsed);
+        }
+    }
+
+    // * Import and export unencrypted files
+
+    /// Import unencrypted file, as a new unsaved file
+    ///
+    /// Attempts to close current file (See `self.attempt_file_close`)
+    ///
+    /// Shows *open file* dialog, then a dialog to delete the original file once it is saved encrypted
+    pub(super) fn file_import(&mut self) {
+        println!("Import");
+
+        if !self.file_can_close() {
+            self.attempting_file_close
+                .set_action(CloseFileAction::ImportFile);
+            return;
+        }
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Text file", &["txt", "md"])
+            .pick_file()
+            .map(|path_buf| path_buf.display().to_string())
+        else {
+            return;
+        };
+
+        match File::import_plaintext(&path) {
+            Ok(mut file) => {
+                file.set_cipher(self.settings.default_cipher);
+                self.file = file;
+                self.file_lock = None;
+                self.pending_lock = None;
+                self.pending_import = Some(path);
+            }
+            Err(error) => self.set_error_message(display_crypto_error(cocoon::Error::Io(error))),
+        }
+    }
+
+    /// Overwrite and remove unencrypted file which was imported
+    pub(super) fn remove_imported_file(&mut self) {
+        let Some(path) = self.imported_path.take() else {
+            return;
+        };
+
+        if let Err(error) = remove_securely(&path) {
+            self.set_error_message(display_crypto_error(cocoon::Error::Io(error)));
+        }
+    }
+
+    /// Export contents without encryption
+    ///
+    /// Shows *save file* dialog, then a warning dialog before exporting
+    pub(super) fn file_export(&mut self) {
+        println!("Export");
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Text file", &["txt", "md"])
+            .save_file()
+            .map(|path_buf| path_buf.display().to_string())
+        {
+            self.attempting_export.set_action(path);
+        }
+    }
+
+    /// Export contents to path of attempt, if warning was accepted
+    pub(super) fn call_export_action(&mut self) {
+        if !self.attempting_export.check_condition(false) {
+            return;
+        }
+
+        if let Some(path) = self.attempting_export.action() {
+            if let Err(error) = self.file.export_decrypted(path) {
+                self.set_error_message(display_crypto_error(cocoon::Error::Io(error)));
+            }
+        }
+        self.attempting_export.reset_attempt();
+    }
+
+    // * Armored text
+
+    /// Copy file to clipboard, encrypted as armored text
+    ///
+    /// Asks for passphrase first, if format uses one and it was not entered for file
+    pub(super) fn copy_armored(&mut self, ctx: &egui::Context) {
+        if self.file.has_data_not_in_format() {
+            self.set_error_message(TEXT_ONLY_ERROR);
+            return;
+        }
+
+        match self.passphrase_for(self.file.path(), self.file.container()) {
+            Some(key) => self.copy_armored_with(ctx, &key),
+            None => self.ask_passphrase(PassphraseAction::CopyArmored, false),
+        }
+    }
+
+    /// Copy file to clipboard, encrypted as armored text with key
+    pub(super) fn copy_armored_with(&mut self, ctx: &egui::Context, key: &str) {
+        match self.file.to_armored(key) {
+            Ok(text) => ctx.output_mut(|output| output.copied_text = text),
+            Err(error) => self.set_error_message(display_crypto_error(error)),
+        }
+    }
+
+    /// Decrypt armored text from clipboard, as a new unsaved file
+    ///
+    /// Attempts to close current file (See `self.attempt_file_close`)
+    pub(super) fn paste_armored(&mut self) {
+        println!("Paste armored text");
+
+        if !self.file_can_close() {
+            self.attempting_file_close
+                .set_action(CloseFileAction::PasteArmored);
+            return;
+        }
+
+        let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("Failed to read clipboard: {:?}", error);
+                self.set_error_message("Clipboard does not contain text");
+                return;
+            }
+        };
+
+        self.open_armored(text, None);
+    }
+
+    /// Decrypt armored text, as a new unsaved file
+    ///
+    /// Text with a passphrase is decrypted with the legacy key if no passphrase is given,
+    ///     and passphrase is asked for if this fails
+    pub(super) fn open_armored(&mut self, text: String, passphrase: Option<String>) {
+        let asks_passphrase =
+            File::detect_armored_container(&text).is_ok_and(Container::asks_passphrase);
+        let key = passphrase
+            .as_deref()
+            .filter(|_| asks_passphrase)
+            .unwrap_or(KEY);
+
+        match File::from_armored(&text, key) {
+            Ok(file) => {
+                self.file = file;
+                self.file_lock = None;
+                self.pending_lock = None;
+                self.pending_import = None;
+            }
+            Err(cocoon::Error::Cryptography) if asks_passphrase => {
+                self.ask_passphrase(PassphraseAction::PasteArmored(text), passphrase.is_some());
+            }
+            Err(error) => self.set_error_message(display_crypto_error(error)),
+        }
     }
 
     // * Handle file close
@@ -213,6 +783,19 @@ impl App {
                     self.file_new();
                     self.reset_close_action();
                 }
+                CloseFileAction::ImportFile => {
+                    self.file_import();
+                    self.reset_close_action();
+                }
+                CloseFileAction::PasteArmored => {
+                    self.paste_armored();
+                    self.reset_close_action();
+                }
+                CloseFileAction::OpenPath(path) => {
+                    let path = path.clone();
+                    self.file_open_path(path);
+                    self.reset_close_action();
+                }
 
                 // This action was registered by the `on_close_event` method
                 // This cannot call `reset_close_action,
@@ -229,8 +812,20 @@ impl App {
     }
 }
 
+/// Add file extension to path, if path has no extension
+fn with_file_extension(mut path: PathBuf, extension: &str) -> PathBuf {
+    if path.extension().is_none() && !extension.is_empty() {
+        path.set_extension(extension);
+    }
+    path
+}
+
+/// Error message if file has data which its format cannot save
+const TEXT_ONLY_ERROR: &str = "This format only saves text. Remove attachments and versions, \
+    and turn off saving undo history and versions, or choose another format";
+
 /// Print error to stderr and returns nice error message for user
-fn display_crypto_error(error: cocoon::Error) -> &'static str {
+pub(super) fn display_crypto_error(error: cocoon::Error) -> &'static str {
     use cocoon::Error::*;
 
     eprintln!("Error! {:#?}", error);
@@ -255,6 +850,12 @@ fn display_crypto_error(error: cocoon::Error) -> &'static str {
 
             io::ErrorKind::PermissionDenied => "Permission denied",
 
+            io::ErrorKind::NotFound => "File not found. It may have been moved or deleted",
+
+            io::ErrorKind::FileTooLarge => {
+                "File is too large to open in the editor. Decrypt it with the command line instead"
+            }
+
             // ... more IO errors can be handled here
             _ => "Unknown file error! Please try again",
         },
@@ -0,0 +1,308 @@
+use std::{path::Path, thread};
+
+use eframe::egui;
+
+use super::{App, ConcurrentMessage};
+use crate::{
+    file::{migrate_directory, MigrationReport, MigrationTarget},
+    KEY,
+};
+
+/// Files which would lose data are not migrated, and are listed as failed in report
+const NOT_MIGRATED_NOTE: &str = "Files with attachments, versions, undo history, padding, compression or a language are not migrated, as age files only save text.";
+
+/// State of migration wizard window
+pub struct MigrationWizard {
+    /// Whether window is open
+    open: bool,
+    /// Directory to migrate, including subdirectories
+    directory: String,
+    /// Whether files are encrypted to recipients, instead of a password
+    use_recipients: bool,
+    /// Password to encrypt files with
+    password: String,
+    /// Password again, to catch typing mistakes
+    confirm_password: String,
+    /// Public keys of recipients, one per line
+    recipients: String,
+    /// Whether files are also encrypted to identity in config directory,
+    ///     so they can be opened by this program
+    include_identity: bool,
+    /// Whether original files are kept, with `.legacy.bak` appended
+    backup: bool,
+    /// Whether migration is running on another thread
+    running: bool,
+    /// Result of last migration, with path of report file
+    ///
+    /// `None` if report could not be written
+    report: Option<(MigrationReport, Option<String>)>,
+    /// Why migration could not start
+    error: Option<String>,
+}
+
+impl Default for MigrationWizard {
+    fn default() -> Self {
+        Self {
+            open: false,
+            directory: String::new(),
+            use_recipients: true,
+            password: String::new(),
+            confirm_password: String::new(),
+            recipients: String::new(),
+            include_identity: true,
+            backup: true,
+            running: false,
+            report: None,
+            error: None,
+        }
+    }
+}
+
+impl MigrationWizard {
+    /// Open window, with directory to migrate if known
+    ///
+    /// Report of last migration is forgotten
+    pub fn open(&mut self, directory: Option<String>) {
+        self.open = true;
+        self.report = None;
+        self.error = None;
+        if let Some(directory) = directory {
+            self.directory = directory;
+        }
+    }
+
+    /// Show report of migration, which finished on another thread
+    pub fn finish(&mut self, report: MigrationReport, report_path: Option<String>) {
+        self.running = false;
+        self.password.clear();
+        self.confirm_password.clear();
+        self.report = Some((report, report_path));
+    }
+
+    /// Get target of migration from inputs
+    ///
+    /// Returns reason if inputs are not valid
+    fn target(&self) -> Result<MigrationTarget, String> {
+        if self.use_recipients {
+            return MigrationTarget::recipients(&self.recipients, self.include_identity)
+                .map_err(|error| error.to_string());
+        }
+
+        if self.password.is_empty() {
+            return Err(String::from("Password is empty"));
+        }
+        if self.password != self.confirm_password {
+            return Err(String::from("Passwords do not match"));
+        }
+        Ok(MigrationTarget::Password(self.password.clone()))
+    }
+}
+
+impl App {
+    /// Render migration wizard window, if open
+    ///
+    /// Files which use the legacy key are re-encrypted on another thread, then a report is shown
+    pub(super) fn render_migration_wizard(&mut self, ctx: &egui::Context) {
+        if self.locked || !self.migration_wizard.open {
+            return;
+        }
+
+        let mut open = self.migration_wizard.open;
+        let mut start = false;
+        let wizard = &mut self.migration_wizard;
+
+        egui::Window::new("Migrate legacy files")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if wizard.running {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Migrating files...");
+                    });
+                    return;
+                }
+
+                // Last step: show what happened to each file
+                if let Some((report, report_path)) = &wizard.report {
+                    ui.strong(format!(
+                        "{} migrated, {} failed",
+                        report.migrated.len(),
+                        report.failed.len()
+                    ));
+                    match report_path {
+                        Some(path) => ui.label(format!("Report written to {}", path)),
+                        None => ui.weak("Report could not be written"),
+                    };
+                    ui.separator();
+
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for path in &report.migrated {
+                                ui.label(format!("✔ {}", path));
+                            }
+                            for (path, reason) in &report.failed {
+                                ui.colored_label(
+                                    ui.visuals().error_fg_color,
+                                    format!("✖ {}: {}", path, reason),
+                                );
+                            }
+                        });
+
+                    ui.separator();
+                    if ui.button("Migrate another directory").clicked() {
+                        wizard.report = None;
+                    }
+                    return;
+                }
+
+                ui.label(
+                    "Files encrypted with the key built into this program can be read by anyone \
+                    with a copy of it. Migrated files are saved as age files.",
+                );
+                ui.separator();
+
+                egui::Grid::new("migration")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Directory");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut wizard.directory)
+                                    .desired_width(220.0),
+                            );
+                            if ui.button("Browse...").clicked() {
+                                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                    wizard.directory = path.display().to_string();
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Encrypt with");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut wizard.use_recipients, true, "Recipients");
+                            ui.selectable_value(&mut wizard.use_recipients, false, "Password");
+                        });
+                        ui.end_row();
+
+                        if wizard.use_recipients {
+                            ui.label("Public keys");
+                            ui.add(
+                                egui::TextEdit::multiline(&mut wizard.recipients)
+                                    .hint_text("age1...")
+                                    .desired_rows(3),
+                            );
+                            ui.end_row();
+
+                            ui.label("");
+                            ui.checkbox(&mut wizard.include_identity, "Include my age identity")
+                                .on_hover_text(
+                                    "Files can only be opened by this program if this is on",
+                                );
+                            ui.end_row();
+                        } else {
+                            ui.label("Password");
+                            ui.add(egui::TextEdit::singleline(&mut wizard.password).password(true));
+                            ui.end_row();
+
+                            ui.label("Confirm password");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut wizard.confirm_password)
+                                    .password(true),
+                            );
+                            ui.end_row();
+                        }
+
+                        ui.label("Backups");
+                        ui.checkbox(&mut wizard.backup, "Keep original files")
+                            .on_hover_text("Originals are copied, with .legacy.bak appended");
+                        ui.end_row();
+                    });
+
+                if !wizard.use_recipients {
+                    ui.weak("The password is asked for when a migrated file is opened.");
+                }
+                ui.weak(NOT_MIGRATED_NOTE);
+                if let Some(error) = &wizard.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready = !wizard.directory.trim().is_empty();
+                    start = ui
+                        .add_enabled(ready, egui::Button::new("Migrate"))
+                        .clicked();
+                });
+            });
+
+        self.migration_wizard.open = open;
+        if start {
+            self.start_migration(ctx);
+        }
+    }
+
+    /// Migrate directory in new thread, and send report to main thread
+    ///
+    /// Open file is closed if it is in directory, so it is not saved over migrated file
+    fn start_migration(&mut self, ctx: &egui::Context) {
+        let target = match self.migration_wizard.target() {
+            Ok(target) => target,
+            Err(error) => {
+                self.migration_wizard.error = Some(error);
+                return;
+            }
+        };
+
+        let directory = self.migration_wizard.directory.trim().to_string();
+        let open_in_directory = self
+            .file
+            .path()
+            .is_some_and(|path| Path::new(path).starts_with(&directory));
+        if open_in_directory {
+            if self.file.is_changed() {
+                self.migration_wizard.error =
+                    Some(String::from("Save or close the open file before migrating"));
+                return;
+            }
+            self.file = Default::default();
+            self.file_lock = None;
+            self.pending_lock = None;
+            self.pending_import = None;
+        }
+
+        self.migration_wizard.error = None;
+        self.migration_wizard.running = true;
+
+        let extension = self.settings.file_extension.clone();
+        let backup = self.migration_wizard.backup;
+        let sender = self.channel.sender.clone();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let report = migrate_directory(&directory, &extension, KEY, &target, backup)
+                .unwrap_or_else(|error| MigrationReport {
+                    failed: vec![(directory.clone(), error.to_string())],
+                    directory,
+                    ..Default::default()
+                });
+
+            let report_path = report
+                .write()
+                .map_err(|error| eprintln!("Failed to write report: {:?}", error))
+                .ok();
+
+            sender
+                .send(ConcurrentMessage::FinishMigration(
+                    Box::new(report),
+                    report_path,
+                ))
+                .expect("Send message");
+            ctx.request_repaint();
+        });
+    }
+}
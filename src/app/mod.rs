@@ -1,31 +1,130 @@
+/// Add, remove, and export binary files stored in file
+mod attachments;
+/// Show changes since file was saved
+mod changes;
+/// Render changes between two texts
+mod diff;
+/// Render editable text of file contents
+mod editor;
+/// Find and replace text in file contents
+mod find;
 /// Custom implementations for `App`
 mod methods;
+/// Re-encrypt files which use the legacy key
+mod migrate;
+/// Search and run commands
+mod palette;
+/// Ask for passphrase of files which do not use the legacy key
+mod passphrase;
+/// Render preferences window
+mod preferences;
+/// Render Markdown preview
+mod preview;
+/// Show how file is encrypted, and change cipher
+mod properties;
 /// Render `App` with `eframe::App` implementation
 mod render;
+/// Render status bar
+mod status;
+/// Render undo history window
+mod undo;
+/// Browse, compare, and restore previous versions of file
+mod versions;
+/// Watch open file for changes by other programs
+mod watch;
 
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use crate::{Attempt, Channel, File};
+use eframe::egui::text::CCursorRange;
+use egui_commonmark::CommonMarkCache;
+
+use self::{
+    attachments::AttachmentsPanel, changes::ChangesView, find::FindBar, migrate::MigrationWizard,
+    palette::CommandPalette, passphrase::PassphrasePrompt, versions::VersionsPanel,
+    watch::FileWatcher,
+};
+use crate::{
+    file::{FileLock, LockOwner, MigrationReport},
+    Attempt, Channel, Command, File, RecentFiles, Settings,
+};
 
 /// Possible messages between threads
 enum ConcurrentMessage {
     /// Save has succeeded
-    FinishConcurrentSave,
+    ///
+    /// Contains the copy of file which was saved
+    FinishConcurrentSave(Box<File>),
+    /// File may have been changed by another program
+    ChangedOnDisk,
+    /// Migration of directory has finished
+    ///
+    /// Contains report, and path of report file if it was written
+    FinishMigration(Box<MigrationReport>, Option<String>),
 }
 
 /// Actions to allow after close attempt passes
 enum CloseFileAction {
     NewFile,
     OpenFile,
+    /// Import unencrypted file as a new file
+    ImportFile,
+    /// Open armored text from clipboard as a new file
+    PasteArmored,
+    /// Open a specific file, such as from recent files
+    OpenPath(String),
     CloseWindow,
 }
 
 /// Main app state
-#[derive(Default)]
 pub struct App {
     /// Current file opened
     file: File,
 
+    /// Lock of current file, so it is not edited by another program
+    ///
+    /// Released when file is closed
+    file_lock: Option<FileLock>,
+
+    /// Path which file is being saved to with *save as*, and lock of that path
+    ///
+    /// Replaces lock of current file once save finishes, so old path stays locked if save fails
+    pending_lock: Option<(String, Option<FileLock>)>,
+
+    /// File which was opened, but is locked by another program, with owner of lock
+    ///
+    /// Shows a dialog to open it read-only
+    lock_conflict: Option<(File, LockOwner)>,
+
+    /// Path of unencrypted file which was imported, until file is saved encrypted
+    ///
+    /// Forgotten if another file is opened before saving
+    pending_import: Option<String>,
+
+    /// Path of unencrypted file which was imported, after file was saved encrypted
+    ///
+    /// Shows a dialog to delete it
+    imported_path: Option<String>,
+
+    /// Attempt to export file without encryption, to a path
+    ///
+    /// Shows a warning dialog, before exporting
+    attempting_export: Attempt<String>,
+
+    /// Whether dialog to choose encryption of age file is open, before saving
+    save_as_age_dialog: bool,
+
+    /// Passphrases entered for files, by path
+    ///
+    /// Only kept in memory until program closes, and never saved
+    passphrases: HashMap<String, String>,
+
+    /// Dialog asking for passphrase, before opening or saving file
+    passphrase_prompt: Option<PassphrasePrompt>,
+
     /// Whether file is currently writing
     writing: Arc<Mutex<bool>>,
 
@@ -38,26 +137,141 @@ pub struct App {
     /// Send messages between threads
     channel: Channel<ConcurrentMessage>,
 
-    /// Display any error message
-    error_message: Arc<Mutex<Option<&'static str>>>,
-}
+    /// List of recently opened files
+    recent_files: RecentFiles,
+
+    /// User preferences
+    settings: Settings,
+
+    /// Whether preferences window is open
+    show_preferences: bool,
+
+    /// Search and run commands
+    command_palette: CommandPalette,
+
+    /// Whether undo history window is open
+    show_undo_history: bool,
+
+    /// Whether document properties window is open
+    show_properties: bool,
+
+    /// Browse previous versions of file
+    versions_panel: VersionsPanel,
 
-// // @ debug
-// impl Default for App {
-//     fn default() -> Self {
-//         Self {
-//             // file: File::open_path_and_decrypt("/home/darcy/Documents/hello.enc", KEY).expect("Open initial file"),
-//             file:Default::default(),
+    /// Add, remove, and export binary files stored in file
+    attachments_panel: AttachmentsPanel,
 
-//             writing: Default::default(),
+    /// Re-encrypt files which use the legacy key
+    migration_wizard: MigrationWizard,
 
-//             attempting_file_close: Default::default(),
+    /// Whether to suggest migrating file which was opened with the legacy key
+    legacy_key_nudge: bool,
 
-//             close_window_on_next_frame: Default::default(),
+    /// Show changes since file was saved
+    changes_view: ChangesView,
 
-//             channel: Default::default(),
+    /// Watches open file for changes by other programs
+    watcher: Option<FileWatcher>,
 
-//             error: Default::default(),
-//         }
-//     }
-// }
+    /// Whether file should be checked for changes by other programs, when not writing
+    disk_check_pending: bool,
+
+    /// Whether file was changed by another program, showing a dialog
+    external_change: bool,
+
+    /// Whether file can be read or written by other users, showing a warning
+    insecure_permissions: bool,
+
+    /// Find and replace text in file contents
+    find_bar: FindBar,
+
+    /// Whether Markdown preview is shown beside editor
+    show_preview: bool,
+
+    /// Cache for rendering Markdown preview
+    markdown_cache: CommonMarkCache,
+
+    /// Vertical scroll position of editor, from `0.0` (top) to `1.0` (bottom)
+    editor_scroll: f32,
+
+    /// Height of Markdown preview content in previous frame
+    preview_height: f32,
+
+    /// Cursor and selection in editor, for status bar
+    ///
+    /// `None` if editor is not focused
+    cursor: Option<CCursorRange>,
+
+    /// Command which is waiting for a key press to set its keybind
+    ///
+    /// Keybinds are disabled while this is `Some`
+    capturing_keybind: Option<Command>,
+
+    /// Time of last edit to file contents, for autosave
+    ///
+    /// `None` if file was not edited since last save
+    last_edit: Option<Instant>,
+
+    /// Time of last user input, for lock timeout
+    last_activity: Instant,
+
+    /// Whether editor is locked, hiding file contents
+    ///
+    /// This is a privacy screen, not a security lock: unlocking needs no password,
+    ///     and file stays decrypted in memory
+    locked: bool,
+
+    /// Display any error message
+    error_message: Arc<Mutex<Option<&'static str>>>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let settings = Settings::load();
+        let mut file = File::default();
+        file.set_cipher(settings.default_cipher);
+
+        Self {
+            file,
+            file_lock: None,
+            pending_lock: None,
+            lock_conflict: None,
+            pending_import: None,
+            imported_path: None,
+            attempting_export: Default::default(),
+            save_as_age_dialog: false,
+            passphrases: HashMap::new(),
+            passphrase_prompt: None,
+            writing: Default::default(),
+            attempting_file_close: Default::default(),
+            close_window_on_next_frame: Default::default(),
+            channel: Default::default(),
+            recent_files: RecentFiles::load(settings.encrypt_recent_files),
+            settings,
+            show_preferences: false,
+            command_palette: Default::default(),
+            show_undo_history: false,
+            show_properties: false,
+            versions_panel: Default::default(),
+            attachments_panel: Default::default(),
+            migration_wizard: Default::default(),
+            legacy_key_nudge: false,
+            changes_view: Default::default(),
+            watcher: None,
+            disk_check_pending: false,
+            external_change: false,
+            insecure_permissions: false,
+            find_bar: Default::default(),
+            show_preview: false,
+            markdown_cache: Default::default(),
+            editor_scroll: 0.0,
+            preview_height: 0.0,
+            cursor: None,
+            capturing_keybind: None,
+            last_edit: None,
+            last_activity: Instant::now(),
+            locked: false,
+            error_message: Default::default(),
+        }
+    }
+}
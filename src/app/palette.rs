@@ -0,0 +1,161 @@
+use eframe::{egui, emath::Align2};
+
+use super::App;
+use crate::Command;
+
+/// State of command palette
+#[derive(Default)]
+pub struct CommandPalette {
+    /// Whether palette is open
+    open: bool,
+    /// Search text
+    query: String,
+    /// Index of highlighted command, in filtered list
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Open palette with empty search, or close if open
+    pub fn toggle(&mut self) {
+        if self.open {
+            self.open = false;
+        } else {
+            *self = Self {
+                open: true,
+                ..Default::default()
+            };
+        }
+    }
+}
+
+impl App {
+    /// Render command palette, if open
+    ///
+    /// Commands run through `run_command`, so disabled commands cannot run
+    pub(super) fn render_command_palette(&mut self, ctx: &egui::Context) {
+        if !self.command_palette.open {
+            return;
+        }
+
+        // Commands matching search, best match first
+        let mut matches: Vec<(i32, Command)> = Command::ALL
+            .iter()
+            .filter(|&&command| command != Command::Palette)
+            .filter_map(|&command| {
+                fuzzy_score(&self.command_palette.query, command.name())
+                    .map(|score| (score, command))
+            })
+            .collect();
+        // Stable sort keeps order of `Command::ALL` for equal scores
+        matches.sort_by_key(|(score, _)| -score);
+
+        let mut command_to_run = None;
+
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, (0.0, 40.0))
+            .show(ctx, |ui| {
+                let palette = &mut self.command_palette;
+
+                // Keys must be consumed before text edit, which would otherwise use them
+                if keys!(ui: Escape) {
+                    palette.open = false;
+                }
+                if keys!(ui: ArrowDown) && palette.selected + 1 < matches.len() {
+                    palette.selected += 1;
+                }
+                if keys!(ui: ArrowUp) {
+                    palette.selected = palette.selected.saturating_sub(1);
+                }
+                if keys!(ui: Enter) {
+                    command_to_run = matches.get(palette.selected).map(|(_, command)| *command);
+                }
+
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut palette.query)
+                        .hint_text("Search commands")
+                        .desired_width(300.0),
+                );
+                search.request_focus();
+                if search.changed() {
+                    palette.selected = 0;
+                }
+
+                ui.separator();
+
+                if matches.is_empty() {
+                    ui.weak("No matching commands");
+                }
+
+                for (index, &(_, command)) in matches.iter().enumerate() {
+                    let enabled = self.command_enabled(command);
+
+                    ui.add_enabled_ui(enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            let row = ui.selectable_label(
+                                index == self.command_palette.selected,
+                                command.name(),
+                            );
+                            if row.clicked() {
+                                command_to_run = Some(command);
+                            }
+
+                            // Show keybind on right side
+                            if let Some(keybind) = self.settings.keybinds.get(command) {
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| ui.weak(ctx.format_shortcut(&keybind)),
+                                );
+                            }
+                        });
+                    });
+                }
+            });
+
+        // Close palette before running, as command may open another window
+        if let Some(command) = command_to_run {
+            self.command_palette.open = false;
+            self.run_command(command, ctx);
+        }
+    }
+}
+
+/// Score how well query matches text, ignoring case
+///
+/// Returns `None` if characters of query do not appear in order in text
+///
+/// Higher score is a better match, preferring consecutive characters and starts of words
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    // Index in text to search from
+    let mut position = 0;
+    // Index of previous matched character
+    let mut previous = None;
+
+    for query_char in query
+        .to_lowercase()
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+    {
+        let offset = text[position..].iter().position(|&ch| ch == query_char)?;
+        let index = position + offset;
+
+        score += 1;
+        // Consecutive characters
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+        // Start of word
+        if index == 0 || text[index - 1] == ' ' {
+            score += 3;
+        }
+
+        previous = Some(index);
+        position = index + 1;
+    }
+
+    Some(score)
+}
@@ -0,0 +1,155 @@
+use eframe::egui;
+
+use super::{render::dialog_window, App};
+use crate::{file::Container, KEY};
+
+/// Action which is waiting for a passphrase
+pub enum PassphraseAction {
+    /// Open file at path
+    Open(String),
+    /// Save file to path
+    Save(String),
+    /// Copy file to clipboard, as armored text
+    CopyArmored,
+    /// Open armored text from clipboard, as a new file
+    PasteArmored(String),
+}
+
+impl PassphraseAction {
+    /// Returns `true` if action encrypts with passphrase, so it is typed twice to catch mistakes
+    fn encrypts(&self) -> bool {
+        matches!(self, Self::Save(_) | Self::CopyArmored)
+    }
+
+    /// Human-readable description of what passphrase is for
+    fn describe(&self) -> String {
+        match self {
+            Self::Open(path) => format!("Enter passphrase of {}", path),
+            Self::Save(path) => format!("Choose passphrase for {}", path),
+            Self::CopyArmored => String::from("Choose passphrase for armored text"),
+            Self::PasteArmored(_) => String::from("Enter passphrase of armored text"),
+        }
+    }
+}
+
+/// Dialog asking for passphrase, before running an action
+pub struct PassphrasePrompt {
+    /// Action to run with passphrase
+    action: PassphraseAction,
+    /// Passphrase being typed
+    passphrase: String,
+    /// Passphrase again, if action encrypts
+    confirm: String,
+    /// Whether last passphrase entered was wrong
+    incorrect: bool,
+}
+
+impl App {
+    /// Get passphrase to encrypt or decrypt file at path with
+    ///
+    /// Formats which do not ask for a passphrase always use the legacy key
+    ///
+    /// `None` if passphrase was not entered for path in this session
+    pub(super) fn passphrase_for(
+        &self,
+        path: Option<&String>,
+        container: Container,
+    ) -> Option<String> {
+        if !container.asks_passphrase() {
+            return Some(KEY.to_string());
+        }
+        path.and_then(|path| self.passphrases.get(path)).cloned()
+    }
+
+    /// Show dialog asking for passphrase, then run action with it
+    ///
+    /// `incorrect` should be `true` if a passphrase was already tried
+    pub(super) fn ask_passphrase(&mut self, action: PassphraseAction, incorrect: bool) {
+        self.passphrase_prompt = Some(PassphrasePrompt {
+            action,
+            passphrase: String::new(),
+            confirm: String::new(),
+            incorrect,
+        });
+    }
+
+    /// Render dialog asking for passphrase, if open
+    pub(super) fn render_passphrase_prompt(&mut self, ctx: &egui::Context) {
+        // Do not run file actions while locked
+        if self.locked {
+            return;
+        }
+        let Some(prompt) = &mut self.passphrase_prompt else {
+            return;
+        };
+
+        let mut submit = false;
+        let mut cancel = false;
+
+        dialog_window("Passphrase").show(ctx, |ui| {
+            ui.label(prompt.action.describe());
+            if prompt.incorrect {
+                ui.colored_label(ui.visuals().error_fg_color, "Incorrect passphrase");
+            }
+
+            let encrypts = prompt.action.encrypts();
+            egui::Grid::new("passphrase")
+                .num_columns(2)
+                .spacing([20.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label("Passphrase");
+                    ui.add(egui::TextEdit::singleline(&mut prompt.passphrase).password(true));
+                    ui.end_row();
+
+                    if encrypts {
+                        ui.label("Confirm passphrase");
+                        ui.add(egui::TextEdit::singleline(&mut prompt.confirm).password(true));
+                        ui.end_row();
+                    }
+                });
+
+            let matches = !encrypts || prompt.passphrase == prompt.confirm;
+            if !matches {
+                ui.weak("Passphrases do not match");
+            }
+            let ready = !prompt.passphrase.is_empty() && matches;
+
+            ui.horizontal(|ui| {
+                submit = ui.add_enabled(ready, egui::Button::new("Ok")).clicked()
+                    || (ready && keys!(ui: Enter));
+                cancel = ui.button("Cancel").clicked() || keys!(ui: Escape);
+            });
+        });
+
+        if cancel {
+            self.passphrase_prompt = None;
+        } else if submit {
+            if let Some(prompt) = self.passphrase_prompt.take() {
+                self.run_passphrase_action(prompt.action, prompt.passphrase, ctx);
+            }
+        }
+    }
+
+    /// Run action which was waiting for passphrase
+    ///
+    /// Passphrase is remembered for path of file, until program closes
+    fn run_passphrase_action(
+        &mut self,
+        action: PassphraseAction,
+        passphrase: String,
+        ctx: &egui::Context,
+    ) {
+        match action {
+            PassphraseAction::Open(path) => {
+                self.passphrases.insert(path.clone(), passphrase);
+                self.open_path(path);
+            }
+            PassphraseAction::Save(path) => {
+                self.passphrases.insert(path.clone(), passphrase);
+                self.file_save_existing(&path, ctx);
+            }
+            PassphraseAction::CopyArmored => self.copy_armored_with(ctx, &passphrase),
+            PassphraseAction::PasteArmored(text) => self.open_armored(text, Some(passphrase)),
+        }
+    }
+}
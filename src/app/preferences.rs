@@ -0,0 +1,282 @@
+use eframe::egui::{self, Event, Key};
+
+use super::App;
+use crate::{
+    commands::{format_keybind, keybind_from_press},
+    file::{age_identity, age_identity_path, Cipher},
+    settings::Theme,
+    Command, Settings,
+};
+
+impl App {
+    /// Render preferences window, if open
+    ///
+    /// Settings are applied live, and saved whenever they change
+    pub(super) fn render_preferences(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_preferences;
+        let previous = self.settings.clone();
+        let mut identity_error = false;
+
+        egui::Window::new("Preferences")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let settings = &mut self.settings;
+
+                egui::Grid::new("preferences")
+                    .num_columns(2)
+                    .spacing([20.0, 8.0])
+                    .show(ui, |ui| {
+                        // * Appearance
+
+                        ui.label("Font size");
+                        ui.add(egui::Slider::new(&mut settings.font_size, 8.0..=32.0));
+                        ui.end_row();
+
+                        ui.label("Theme");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut settings.theme, Theme::Dark, "Dark");
+                            ui.selectable_value(&mut settings.theme, Theme::Light, "Light");
+                        });
+                        ui.end_row();
+
+                        ui.label("Wrap lines");
+                        ui.checkbox(&mut settings.wrap, "");
+                        ui.end_row();
+
+                        // * Saving and locking
+
+                        ui.label("Autosave");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.autosave, "");
+                            ui.add_enabled(
+                                settings.autosave,
+                                egui::DragValue::new(&mut settings.autosave_delay)
+                                    .clamp_range(1..=600)
+                                    .prefix("after ")
+                                    .suffix(" s"),
+                            );
+                        });
+                        ui.end_row();
+
+                        ui.label("Lock after");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut settings.lock_timeout)
+                                    .clamp_range(0..=240)
+                                    .suffix(" min"),
+                            );
+                            if settings.lock_timeout == 0 {
+                                ui.weak("(never)");
+                            }
+                        })
+                        .response
+                        .on_hover_text("Hides contents, like a privacy screen. Unlocking does not need a password");
+                        ui.end_row();
+
+                        // * Files
+
+                        ui.label("Default directory");
+                        ui.horizontal(|ui| {
+                            match &settings.default_dir {
+                                Some(dir) => ui.monospace(dir),
+                                None => ui.weak("Documents"),
+                            };
+
+                            if ui.button("Browse...").clicked() {
+                                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                                    settings.default_dir = Some(dir.display().to_string());
+                                }
+                            }
+                            if settings.default_dir.is_some() && ui.button("Reset").clicked() {
+                                settings.default_dir = None;
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("File extension");
+                        if ui
+                            .add(
+                                egui::TextEdit::singleline(&mut settings.file_extension)
+                                    .desired_width(60.0),
+                            )
+                            .changed()
+                        {
+                            // Extension is stored without leading dot
+                            settings.file_extension =
+                                settings.file_extension.trim_start_matches('.').to_string();
+                        }
+                        ui.end_row();
+
+                        // Only applies to new files, and formats which support a choice
+                        ui.label("Default cipher");
+                        ui.horizontal(|ui| {
+                            for cipher in Cipher::ALL {
+                                ui.selectable_value(
+                                    &mut settings.default_cipher,
+                                    cipher,
+                                    cipher.name(),
+                                );
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Encrypt recent files");
+                        ui.checkbox(&mut settings.encrypt_recent_files, "")
+                            .on_hover_text("Paths of opened files are saved in config directory");
+                        ui.end_row();
+
+                        // Identity for age files, which are not encrypted with a passphrase
+                        ui.label("age identity");
+                        ui.horizontal(|ui| {
+                            match age_identity_path() {
+                                Some(path) => ui.monospace(path.display().to_string()),
+                                None => ui.weak("No config directory"),
+                            };
+
+                            // Creates identity, if it does not exist
+                            if ui
+                                .button("Copy public key")
+                                .on_hover_text("Others can encrypt age files for you with this key")
+                                .clicked()
+                            {
+                                match age_identity() {
+                                    Ok(identity) => ui.output_mut(|output| {
+                                        output.copied_text = identity.to_public().to_string();
+                                    }),
+                                    Err(error) => {
+                                        eprintln!("Failed to read age identity: {:?}", error);
+                                        identity_error = true;
+                                    }
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        // * Window
+
+                        ui.label("Window size");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut settings.window_width)
+                                    .clamp_range(200.0..=4000.0),
+                            );
+                            ui.label("x");
+                            ui.add(
+                                egui::DragValue::new(&mut settings.window_height)
+                                    .clamp_range(200.0..=4000.0),
+                            );
+                            ui.weak("(on next start)");
+                        });
+                        ui.end_row();
+                    });
+
+                ui.separator();
+
+                // * Keybinds
+
+                ui.strong("Keybinds");
+
+                egui::Grid::new("keybinds")
+                    .num_columns(3)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for &command in Command::ALL {
+                            let keybind = settings.keybinds.get(command);
+
+                            ui.label(command.name());
+
+                            // Click to wait for a key press
+                            let text = if self.capturing_keybind == Some(command) {
+                                String::from("Press a key...")
+                            } else {
+                                keybind
+                                    .as_ref()
+                                    .map(format_keybind)
+                                    .unwrap_or_else(|| String::from("Unbound"))
+                            };
+                            if ui.button(text).clicked() {
+                                self.capturing_keybind = Some(command);
+                            }
+
+                            ui.horizontal(|ui| {
+                                if keybind.is_some() && ui.small_button("Clear").clicked() {
+                                    settings.keybinds.set(command, None);
+                                }
+                                if keybind != command.default_keybind()
+                                    && ui.small_button("Reset").clicked()
+                                {
+                                    settings.keybinds.set(command, command.default_keybind());
+                                }
+
+                                // Only the first of any conflicting commands will run
+                                let conflicts = settings.keybinds.conflicts(command);
+                                if !conflicts.is_empty() {
+                                    let names: Vec<_> =
+                                        conflicts.iter().map(|command| command.name()).collect();
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        format!("Conflicts with {}", names.join(", ")),
+                                    );
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                // Set keybind from key press
+                if let Some(command) = self.capturing_keybind {
+                    let pressed = ui.input(|input| {
+                        input.events.iter().find_map(|event| match event {
+                            Event::Key {
+                                key,
+                                pressed: true,
+                                modifiers,
+                                ..
+                            } => Some((*key, *modifiers)),
+                            _ => None,
+                        })
+                    });
+
+                    match pressed {
+                        // Cancel
+                        Some((Key::Escape, modifiers)) if modifiers.is_none() => {
+                            self.capturing_keybind = None;
+                        }
+                        Some((key, modifiers)) => {
+                            settings
+                                .keybinds
+                                .set(command, Some(keybind_from_press(modifiers, key)));
+                            self.capturing_keybind = None;
+                        }
+                        None => (),
+                    }
+                }
+
+                ui.separator();
+
+                if ui.button("Reset to defaults").clicked() {
+                    *settings = Settings::default();
+                }
+            });
+
+        self.show_preferences = open;
+
+        if identity_error {
+            self.set_error_message("Failed to read age identity file");
+        }
+
+        // Stop waiting for key press if window was closed
+        if !open {
+            self.capturing_keybind = None;
+        }
+
+        // Save if anything changed this frame
+        if self.settings != previous {
+            self.recent_files
+                .set_encrypted(self.settings.encrypt_recent_files);
+            self.settings.save();
+        }
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests;
+
+use eframe::egui;
+use egui_commonmark::CommonMarkViewer;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+use super::App;
+
+impl App {
+    /// Render Markdown preview of file contents, in side panel
+    ///
+    /// Scrolls to the same relative position as the editor
+    pub(super) fn render_preview(&mut self, ui: &mut egui::Ui) {
+        let markdown = strip_images(self.file.contents());
+
+        egui::SidePanel::right("markdown_preview")
+            .resizable(true)
+            .default_width(ui.available_width() / 2.0)
+            .show_inside(ui, |ui| {
+                // Use height from previous frame, as content has not been laid out yet
+                let max_offset = (self.preview_height - ui.available_height()).max(0.0);
+
+                let output = egui::ScrollArea::vertical()
+                    .id_source("markdown_preview")
+                    .auto_shrink([false, false])
+                    .vertical_scroll_offset(self.editor_scroll * max_offset)
+                    .show(ui, |ui| {
+                        CommonMarkViewer::new("markdown_preview").show(
+                            ui,
+                            &mut self.markdown_cache,
+                            &markdown,
+                        );
+                    });
+
+                if output.content_size.y != self.preview_height {
+                    self.preview_height = output.content_size.y;
+                    ui.ctx().request_repaint();
+                }
+            });
+    }
+}
+
+/// Replace images with their alt text
+///
+/// The renderer would otherwise load image files, which could be on a network drive,
+///     revealing that the file was opened
+fn strip_images(markdown: &str) -> String {
+    let mut stripped = String::new();
+    // Position in markdown which has been copied
+    let mut position = 0;
+    // Number of images which current event is inside
+    // Images can be nested in alt text of other images
+    let mut depth = 0;
+    // Alt text of outermost image, if inside image
+    let mut alt_text = String::new();
+
+    for (event, range) in Parser::new_ext(markdown, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Image(..)) => {
+                if depth == 0 {
+                    stripped.push_str(&markdown[position..range.start]);
+                    position = range.end;
+                    alt_text.clear();
+                }
+                depth += 1;
+            }
+            Event::End(Tag::Image(..)) => {
+                depth -= 1;
+                if depth == 0 {
+                    stripped.push_str(&format!("\\[image: {}\\]", escape(alt_text.trim())));
+                }
+            }
+            Event::Text(text) | Event::Code(text) if depth > 0 => alt_text.push_str(&text),
+            _ => (),
+        }
+    }
+
+    stripped.push_str(&markdown[position..]);
+    stripped
+}
+
+/// Escape characters of alt text, so it cannot form a link or image
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for char in text.chars() {
+        if matches!(char, '\\' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn strip_inline_images() {
+    assert_eq!(
+        strip_images("Before ![a cat](cat.png) after"),
+        "Before \\[image: a cat\\] after"
+    );
+    assert_eq!(strip_images("No images"), "No images");
+    assert_eq!(
+        strip_images("![`code`](a.png) and ![second](b.png)"),
+        "\\[image: code\\] and \\[image: second\\]"
+    );
+}
+
+#[test]
+fn strip_nested_images() {
+    assert_eq!(strip_images("![a ![b](c)](d)"), "\\[image: a b\\]");
+    assert_eq!(
+        strip_images("![a ![b ![c](d)](e)](f) end"),
+        "\\[image: a b c\\] end"
+    );
+}
+
+#[test]
+fn strip_reference_images() {
+    let markdown = "![alt][logo]\n\n[logo]: https://example.com/logo.png";
+    let stripped = strip_images(markdown);
+    assert!(stripped.starts_with("\\[image: alt\\]"));
+    assert!(!stripped.contains("![alt]"));
+}
+
+#[test]
+fn strip_images_in_links() {
+    assert_eq!(
+        strip_images("[![badge](badge.svg)](https://example.com)"),
+        "[\\[image: badge\\]](https://example.com)"
+    );
+}
+
+#[test]
+fn escape_alt_text() {
+    // Alt text cannot form a new image
+    assert_eq!(
+        strip_images("![\\!\\[b\\](c)](d)"),
+        "\\[image: !\\[b\\](c)\\]"
+    );
+}
@@ -0,0 +1,88 @@
+use eframe::egui;
+
+use super::App;
+use crate::file::Cipher;
+
+impl App {
+    /// Render document properties window, if open
+    ///
+    /// Shows how file is encrypted, and cipher can be changed here
+    pub(super) fn render_properties(&mut self, ctx: &egui::Context) {
+        // Do not show anything about file while locked
+        if self.locked {
+            return;
+        }
+
+        let mut open = self.show_properties;
+        let container = self.file.container();
+        let mut cipher = self.file.cipher();
+
+        egui::Window::new("Document properties")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("properties")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Path");
+                        match self.file.path() {
+                            Some(path) => ui.monospace(path),
+                            None => ui.weak("Not saved"),
+                        };
+                        ui.end_row();
+
+                        ui.label("Format");
+                        ui.label(container.name());
+                        ui.end_row();
+
+                        // Applies on next save, and is read from file when opened
+                        ui.label("Cipher");
+                        if container.supports_cipher() {
+                            ui.add_enabled_ui(!self.file.is_read_only(), |ui| {
+                                egui::ComboBox::from_id_source("cipher")
+                                    .selected_text(cipher.name())
+                                    .show_ui(ui, |ui| {
+                                        for option in Cipher::ALL {
+                                            ui.selectable_value(&mut cipher, option, option.name());
+                                        }
+                                    });
+                            });
+                        } else {
+                            ui.label(container.algorithm(cipher))
+                                .on_hover_text("Cipher cannot be changed for this format");
+                        }
+                        ui.end_row();
+
+                        ui.label("Key derivation");
+                        ui.label(container.key_derivation());
+                        ui.end_row();
+
+                        // Formats which only save text are not padded or compressed
+                        ui.label("Compression");
+                        if container.is_text_only() {
+                            ui.weak("Not supported");
+                        } else {
+                            ui.label(if self.file.compress() { "On" } else { "Off" });
+                        }
+                        ui.end_row();
+
+                        ui.label("Padding");
+                        if container.is_text_only() {
+                            ui.weak("Not supported");
+                        } else {
+                            ui.label(self.file.padding().name());
+                        }
+                        ui.end_row();
+
+                        ui.label("Armored");
+                        ui.label(if self.file.is_armored() { "Yes" } else { "No" });
+                        ui.end_row();
+                    });
+            });
+
+        self.file.set_cipher(cipher);
+        self.show_properties = open;
+    }
+}
@@ -1,9 +1,7 @@
-use eframe::{
-    egui::{self, TextEdit},
-    emath::Align2,
-};
+use eframe::{egui, emath::Align2};
 
 use super::{App, CloseFileAction, ConcurrentMessage};
+use crate::{file::Container, Command};
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
@@ -13,79 +11,155 @@ impl eframe::App for App {
             frame.close();
         }
 
+        // * Settings, autosave, and lock
+
+        self.apply_settings(ctx);
+        self.check_lock_timeout(ctx);
+        self.autosave(ctx);
+
         // * Handle concurrent messages
 
-        if let Ok(msg) = self.channel.receiver.try_recv() {
+        self.update_watcher(ctx);
+
+        // All messages are handled, so a finished save is known before checking for changes on disk
+        while let Ok(msg) = self.channel.receiver.try_recv() {
             match msg {
-                ConcurrentMessage::FinishConcurrentSave => {
+                ConcurrentMessage::FinishConcurrentSave(saved) => {
                     println!("Save finished!");
-                    self.file.force_set_saved();
+                    // Release lock of old path, now that file is saved to new path
+                    if let Some((path, lock)) = self.pending_lock.take() {
+                        if saved.path() == Some(&path) {
+                            self.file_lock = lock;
+                        } else {
+                            self.pending_lock = Some((path, lock));
+                        }
+                    }
+
+                    self.file.finish_save(*saved);
+
+                    // Original of imported file can be deleted, now that it is saved encrypted
+                    if let Some(path) = self.pending_import.take() {
+                        self.imported_path = Some(path);
+                    }
+
+                    // Remember file, as it may have been saved to a new path
+                    if let Some(path) = self.file.path().cloned() {
+                        self.recent_files.add(&path);
+                    }
 
                     if self.attempting_file_close.is_attempting() {
                         self.call_close_action();
                     }
                 }
+
+                ConcurrentMessage::ChangedOnDisk => self.disk_check_pending = true,
+
+                ConcurrentMessage::FinishMigration(report, report_path) => {
+                    self.migration_wizard.finish(*report, report_path);
+                }
             }
         }
 
+        self.check_disk_changes();
+
         // * Render main window
 
         // Whether the file is currently writing on a different thread
         let concurrently_writing = *self.writing.lock().unwrap();
 
+        // Run commands from keybinds, before any widget can consume keys
+        self.run_keybinds(ctx);
+
+        // Must be rendered before central panel
+        self.render_status_bar(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Encrypted text editor");
 
             // File actions and status
             ui.horizontal(|ui| {
-                /// Create new action, with button and keybind
-                macro_rules! action_button_and_keybind {
-                    (
-                        // Title of button
-                        $title: expr,
-                        // Keybind
-                        ($($keybind:tt)*),
-                        // Condition for button and keybind to be enabled
-                        if $condition: expr =>
-                        // Action to run
-                        $($action:tt)*
-                    ) => {{
-                        // Condition also requires that file is not writing on another thread
-                        let condition = $condition && !concurrently_writing;
-
-                        // Create button with title, that is only enabled if `condition` is true
-                        let button = ui.add_enabled(condition, egui::Button::new($title));
-
-                        // Create keybind, if condition is true
-                        // See `keys!` macro
-                        let keybind_active = (keys!(ui: $($keybind)*) && condition);
-
-                        // If button is clicked, or keybind is active, run condition
-                        if button.clicked() || keybind_active {
-                            $($action)*
-                        }
-                    }};
+                // Buttons for file commands
+                for &command in &[Command::Save, Command::SaveAs, Command::Open, Command::New] {
+                    self.command_button(ui, command);
                 }
 
-                // Create actions from macro
-                action_button_and_keybind!( "Save", (CTRL + S), if !self.file.is_registered_and_saved() => {
-                    self.file_save_or_save_as(ctx);
-                });
-                action_button_and_keybind!( "Save As", (CTRL + SHIFT + S), if true => {
-                    self.file_save_as(ctx);
+                // List of recent files
+                ui.add_enabled_ui(!concurrently_writing, |ui| {
+                    ui.menu_button("Recent", |ui| {
+                        if self.recent_files.is_empty() {
+                            ui.label("No recent files");
+                            return;
+                        }
+
+                        if let Some(path) = self.recent_files_list(ui) {
+                            ui.close_menu();
+                            self.file_open_path(path);
+                        }
+
+                        ui.separator();
+                        if ui.button("Clear recent files").clicked() {
+                            ui.close_menu();
+                            self.recent_files.clear();
+                        }
+                    });
                 });
-                action_button_and_keybind!( "Open", (CTRL + O), if true => {
-                    self.file_open();
+
+                // Convert to or from unencrypted files and armored text
+                ui.menu_button("Import/Export", |ui| {
+                    for &command in &[
+                        Command::SaveAsAge,
+                        Command::Import,
+                        Command::Export,
+                        Command::CopyArmored,
+                        Command::PasteArmored,
+                        Command::Migrate,
+                    ] {
+                        if self.command_button(ui, command) {
+                            ui.close_menu();
+                        }
+                    }
                 });
-                action_button_and_keybind!( "New", (CTRL + N), if !self.file.is_unregistered_and_unchanged() => {
-                    self.file_new();
+
+                // Undo and version history
+                ui.menu_button("Edit", |ui| {
+                    for &command in &[
+                        Command::Undo,
+                        Command::Redo,
+                        Command::UndoHistory,
+                        Command::Versions,
+                        Command::Attachments,
+                        Command::Properties,
+                    ] {
+                        if self.command_button(ui, command) {
+                            ui.close_menu();
+                        }
+                    }
                 });
-                
+
+                self.command_button(ui, Command::Preferences);
+
                 // Show filepath if file is registered
                 if let Some(path) = self.file.path() {
                     ui.monospace(path);
                 }
 
+                // Read-only mode, which cannot be turned off if forced
+                match self.file.read_only() {
+                    Some(read_only) if read_only.is_forced() => {
+                        ui.strong("Read-only")
+                            .on_hover_text(read_only.description());
+                    }
+                    read_only => {
+                        if ui
+                            .selectable_label(read_only.is_some(), "Read-only")
+                            .on_hover_text("Prevent accidental edits")
+                            .clicked()
+                        {
+                            self.run_command(Command::ReadOnly, ctx);
+                        }
+                    }
+                }
+
                 // Save state
                 ui.label(if concurrently_writing {
                     // File is currently being written to
@@ -100,16 +174,52 @@ impl eframe::App for App {
                     // File is unregistered
                     ""
                 });
+
+                // See what changed since save
+                if self.file.path().is_some() && self.file.is_changed() {
+                    self.command_button(ui, Command::ShowChanges);
+                }
             });
 
-            // Editable text of file contents
-            let edit_contents = TextEdit::multiline(self.file.contents_mut()).code_editor();
-            let edit_contents = ui.add_sized( ui.available_size(), edit_contents);
+            // Lock screen
+            // Hide file contents until unlocked
+            if self.locked {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(ui.available_height() / 3.0);
+                    ui.heading("Locked");
+                    ui.weak("Contents are hidden until unlocked. This does not protect the file from anyone who can use this computer.");
+
+                    if ui.button("Unlock").clicked() {
+                        self.locked = false;
+                    }
+                });
+                return;
+            }
+
+            // Start screen
+            // Show recent files if no file is open
+            if self.file.is_unregistered_and_unchanged() && !self.recent_files.is_empty() {
+                ui.group(|ui| {
+                    ui.strong("Recent files");
 
-            // Set save state to unsaved if text was changed
-            if edit_contents.changed() {
-                self.file.mark_as_unsaved();
+                    ui.add_enabled_ui(!concurrently_writing, |ui| {
+                        if let Some(path) = self.recent_files_list(ui) {
+                            self.file_open_path(path);
+                        }
+                    });
+                });
             }
+
+            // Find and replace
+            self.render_find_bar(ui);
+
+            // Markdown preview, beside editor
+            if self.show_preview {
+                self.render_preview(ui);
+            }
+
+            // Editable text of file contents
+            self.render_editor(ui);
         });
 
         // * Render popup windows
@@ -148,6 +258,11 @@ impl eframe::App for App {
                             self.reset_close_action();
                         }
 
+                        // See what would be lost, before choosing
+                        if self.file.path().is_some() && ui.button("Show changes").clicked() {
+                            self.show_changes();
+                        }
+
                         // Save file and close
                         if ui.button("Save").clicked() {
                             // Save (concurrently)
@@ -162,6 +277,216 @@ impl eframe::App for App {
             }
         }
 
+        // Choose encryption of age file, before choosing path
+        if self.save_as_age_dialog && !self.locked {
+            dialog_window("Save as age file").show(ctx, |ui| {
+                ui.label("Files can be opened with the age command line tool.");
+
+                let mut container = None;
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Passphrase")
+                        .on_hover_text("Decrypt with a passphrase, which is asked for when saving")
+                        .clicked()
+                    {
+                        container = Some(Container::AgePassphrase);
+                    }
+                    if ui
+                        .button("X25519 identity")
+                        .on_hover_text("Decrypt with the identity file shown in preferences")
+                        .clicked()
+                    {
+                        container = Some(Container::AgeX25519);
+                    }
+                    if ui.button("Cancel").clicked() || keys!(ui: Escape) {
+                        self.save_as_age_dialog = false;
+                    }
+                });
+
+                if let Some(container) = container {
+                    self.save_as_age_dialog = false;
+                    self.file_save_as_age(ctx, container);
+                }
+            });
+        }
+
+        // Passphrase of file, before opening or saving
+        self.render_passphrase_prompt(ctx);
+
+        // Imported file is still on disk, unencrypted
+        if let Some(path) = &self.imported_path {
+            let message = format!("The original file is not encrypted: {}", path);
+
+            dialog_window("Delete original file?").show(ctx, |ui| {
+                ui.label(message);
+                ui.label(
+                    "It will be overwritten before it is deleted. Copies may remain on some disks.",
+                );
+
+                ui.horizontal(|ui| {
+                    if ui.button("Delete securely").clicked() {
+                        self.remove_imported_file();
+                    }
+                    if ui.button("Keep").clicked() || keys!(ui: Escape) {
+                        self.imported_path = None;
+                    }
+                });
+            });
+        }
+
+        // Exporting file without encryption
+        if let Some(path) = self.attempting_export.action() {
+            let message = format!("Contents will be saved unencrypted to {}", path);
+
+            dialog_window("Export decrypted copy?").show(ctx, |ui| {
+                ui.label(message);
+                ui.label("Anyone with access to this file can read it. Attachments and history are not exported.");
+
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        // Override warning, and try action again
+                        self.attempting_export.override_condition();
+                        self.call_export_action();
+                    }
+                    if ui.button("Cancel").clicked() || keys!(ui: Escape) {
+                        self.attempting_export.reset_attempt();
+                    }
+                });
+            });
+        }
+
+        // File is being edited by another program
+        if let Some((_, owner)) = &self.lock_conflict {
+            let message = format!("This file is being edited by {}.", owner);
+            let mut open_read_only = false;
+            let mut cancel = false;
+
+            dialog_window("File is locked").show(ctx, |ui| {
+                ui.label(message);
+                ui.label("It can be opened read-only, without making changes.");
+
+                ui.horizontal(|ui| {
+                    open_read_only = ui.button("Open read-only").clicked();
+                    cancel = ui.button("Cancel").clicked() || keys!(ui: Escape);
+                });
+            });
+
+            if open_read_only {
+                if let Some((file, _)) = self.lock_conflict.take() {
+                    self.open_read_only(file);
+                }
+            } else if cancel {
+                self.lock_conflict = None;
+            }
+        }
+
+        // File can be read by other users
+        if self.insecure_permissions {
+            dialog_window("Insecure file permissions").show(ctx, |ui| {
+                ui.label("Other users on this computer can read or change this file.");
+
+                ui.horizontal(|ui| {
+                    if ui.button("Fix permissions").clicked() {
+                        self.fix_permissions();
+                    }
+                    if ui.button("Ignore").clicked() || keys!(ui: Escape) {
+                        self.insecure_permissions = false;
+                    }
+                });
+            });
+        }
+
+        // File uses the shared key built into this program
+        // Shown after permissions warning, so dialogs do not overlap
+        if self.legacy_key_nudge && !self.insecure_permissions && !self.locked {
+            dialog_window("File uses the legacy key").show(ctx, |ui| {
+                ui.label("This file is encrypted with the key built into this program.");
+                ui.label("Anyone with a copy of this program can read it.");
+
+                // Migration would fail, so tell why before it is tried
+                let lost = self.file.data_not_in_text_only();
+                if !lost.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "It cannot be migrated, as age files cannot save its {}.",
+                            lost.join(", ")
+                        ),
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Migrate...").clicked() {
+                        self.migrate_open_file();
+                    }
+                    if ui.button("Later").clicked() || keys!(ui: Escape) {
+                        self.legacy_key_nudge = false;
+                    }
+                });
+            });
+        }
+
+        // File was changed by another program
+        if self.external_change {
+            dialog_window("File was changed by another program").show(ctx, |ui| {
+                let dirty = self.file.is_changed();
+
+                ui.label(if dirty {
+                    "Your unsaved changes conflict with the changes on disk."
+                } else {
+                    "Reload to see the changes."
+                });
+
+                ui.horizontal(|ui| {
+                    // Discard changes in editor
+                    if ui.button("Reload").clicked() {
+                        self.reload_file();
+                        self.external_change = false;
+                    }
+
+                    if dirty {
+                        // Replace file on disk with changes in editor
+                        if ui.button("Overwrite").clicked() {
+                            self.file.ignore_changes_on_disk();
+                            self.external_change = false;
+                            self.file_save_or_save_as(ctx);
+                        }
+
+                        // Compare with file on disk
+                        if ui.button("Show changes").clicked() {
+                            self.show_changes();
+                        }
+                    }
+
+                    // Saving will show this dialog again, unless ignored
+                    if ui.button("Ignore").clicked() || keys!(ui: Escape) {
+                        self.file.ignore_changes_on_disk();
+                        self.external_change = false;
+                    }
+                });
+            });
+        }
+
+        // Undo history
+        self.render_undo_history(ctx);
+
+        // Version history
+        self.render_versions(ctx);
+        self.render_attachments(ctx);
+
+        // Encryption of file
+        self.render_properties(ctx);
+        self.render_migration_wizard(ctx);
+
+        // Changes since save
+        self.render_changes(ctx);
+
+        // Preferences
+        self.render_preferences(ctx);
+
+        // Command palette
+        self.render_command_palette(ctx);
+
         // Error message popup
         if let Some(error_msg) = self.get_error_message() {
             dialog_window("Error").show(ctx, |ui| {
@@ -187,8 +512,53 @@ impl eframe::App for App {
     }
 }
 
+impl App {
+    /// Show button which runs command
+    ///
+    /// Button is disabled if command is not allowed to run, and shows keybind on hover
+    ///
+    /// Returns `true` if button was clicked
+    fn command_button(&mut self, ui: &mut egui::Ui, command: Command) -> bool {
+        let button = ui.add_enabled(
+            self.command_enabled(command),
+            egui::Button::new(command.name()),
+        );
+
+        let button = match self.settings.keybinds.get(command) {
+            Some(keybind) => {
+                let keybind = ui.ctx().format_shortcut(&keybind);
+                button
+                    .on_hover_text(keybind.clone())
+                    .on_disabled_hover_text(keybind)
+            }
+            None => button,
+        };
+
+        if button.clicked() {
+            self.run_command(command, ui.ctx());
+            return true;
+        }
+        false
+    }
+
+    /// Show a button for each recent file
+    ///
+    /// Returns path of file that was clicked
+    fn recent_files_list(&self, ui: &mut egui::Ui) -> Option<String> {
+        let mut clicked = None;
+
+        for path in self.recent_files.paths() {
+            if ui.button(path).clicked() {
+                clicked = Some(path.clone());
+            }
+        }
+
+        clicked
+    }
+}
+
 /// Create a simple reusable popup dialog window
-fn dialog_window(title: &str) -> egui::Window {
+pub(super) fn dialog_window(title: &str) -> egui::Window {
     egui::Window::new(title)
         .collapsible(false)
         .resizable(false)
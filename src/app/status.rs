@@ -0,0 +1,143 @@
+use eframe::egui;
+
+use super::App;
+use crate::{
+    file::{Container, Padding},
+    highlight::Language,
+    stats::{line_and_column, TextStats},
+};
+
+/// Shown on options which do not apply to formats that only save text
+const TEXT_ONLY_HINT: &str = "This format only saves text, without padding or compression";
+
+impl App {
+    /// Render status bar at bottom of window
+    ///
+    /// Shows cursor position, text statistics, language, and encryption of file
+    ///
+    /// Language of file can be changed here
+    pub(super) fn render_status_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // Do not show anything about contents while locked
+                if !self.locked {
+                    let contents = self.file.contents();
+
+                    // Cursor position and selection
+                    if let Some(cursor) = self.cursor {
+                        let (line, column) = line_and_column(contents, cursor.primary.index);
+                        ui.label(format!("Ln {}, Col {}", line, column));
+
+                        let selected = cursor.primary.index.abs_diff(cursor.secondary.index);
+                        if selected > 0 {
+                            ui.label(format!("({} selected)", selected));
+                        }
+                        ui.separator();
+                    }
+
+                    let stats = TextStats::new(contents);
+                    ui.label(format!(
+                        "{} characters, {} words, {} lines",
+                        stats.chars, stats.words, stats.lines
+                    ));
+                    ui.separator();
+
+                    if !stats.line_ending.name().is_empty() {
+                        ui.label(stats.line_ending.name());
+                        ui.separator();
+                    }
+
+                    // Language is saved in file, so cannot be changed if read-only
+                    // Formats which only save text do not save language, but it is still highlighted
+                    let mut language = self.file.language();
+                    ui.add_enabled_ui(!self.file.is_read_only(), |ui| {
+                        let response = egui::ComboBox::from_id_source("language")
+                            .selected_text(language.name())
+                            .show_ui(ui, |ui| {
+                                for &option in Language::ALL {
+                                    ui.selectable_value(&mut language, option, option.name());
+                                }
+                            })
+                            .response;
+                        if self.file.container().is_text_only() {
+                            response.on_hover_text("Language is not saved in this format");
+                        }
+                    });
+                    self.file.set_language(language);
+                    ui.separator();
+                }
+
+                if self.file.is_armored() {
+                    ui.label("Armored")
+                        .on_hover_text("File is saved as text, instead of binary");
+                }
+
+                // Format of encrypted file, which applies on next save
+                let mut container = self.file.container();
+                let mut compress = self.file.compress();
+                let mut padding = self.file.padding();
+                let editable = !self.file.is_read_only();
+
+                // Formats which only save text are not padded or compressed
+                let text_only = container.is_text_only();
+                ui.add_enabled_ui(editable && !text_only, |ui| {
+                    let padding_response = egui::ComboBox::from_id_source("padding")
+                        .selected_text(padding.name())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut padding, Padding::None, "None");
+                            ui.selectable_value(&mut padding, Padding::PowerOfTwo, "Power of two");
+                            let bucket = match padding {
+                                Padding::Bucket(size) => size,
+                                _ => Padding::DEFAULT_BUCKET,
+                            };
+                            ui.selectable_value(&mut padding, Padding::Bucket(bucket), "Buckets");
+
+                            // Size of buckets, in KiB
+                            if let Padding::Bucket(size) = &mut padding {
+                                let mut kib = *size / 1024;
+                                ui.add(
+                                    egui::DragValue::new(&mut kib)
+                                        .clamp_range(1..=1024)
+                                        .suffix(" KiB"),
+                                );
+                                *size = kib * 1024;
+                            }
+                        })
+                        .response
+                        .on_hover_text("Pad contents before encryption, to hide their length");
+                    let compress_response = ui
+                        .toggle_value(&mut compress, "Compressed")
+                        .on_hover_text("Compress contents before encryption");
+
+                    if text_only {
+                        padding_response.on_disabled_hover_text(TEXT_ONLY_HINT);
+                        compress_response.on_disabled_hover_text(TEXT_ONLY_HINT);
+                    }
+                });
+
+                ui.add_enabled_ui(editable, |ui| {
+                    egui::ComboBox::from_id_source("container")
+                        .selected_text(self.file.encryption_mode())
+                        .show_ui(ui, |ui| {
+                            let cipher = self.file.cipher();
+                            for option in Container::ALL {
+                                ui.selectable_value(
+                                    &mut container,
+                                    option,
+                                    option.describe(cipher),
+                                );
+                            }
+                        });
+                    if self.file.has_data_not_in_format() {
+                        ui.colored_label(ui.visuals().warn_fg_color, "⚠").on_hover_text(
+                            "This format only saves text, without undo history, versions or attachments",
+                        );
+                    }
+                });
+                self.file.set_container(container);
+                self.file.set_compress(compress);
+                self.file.set_padding(padding);
+            });
+        });
+    }
+}
@@ -0,0 +1,70 @@
+use eframe::egui;
+
+use super::App;
+
+impl App {
+    /// Render undo history window, if open
+    ///
+    /// Clicking an edit undoes or redoes edits until it is the last applied edit
+    pub(super) fn render_undo_history(&mut self, ctx: &egui::Context) {
+        // Do not show edits while locked
+        if self.locked {
+            return;
+        }
+
+        let mut open = self.show_undo_history;
+        let mut jump_to = None;
+
+        egui::Window::new("Undo history")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                // History can be seen, but not changed
+                if self.file.is_read_only() {
+                    ui.weak(
+                        self.file
+                            .read_only()
+                            .map_or("", |read_only| read_only.description()),
+                    );
+                    ui.set_enabled(false);
+                }
+
+                let mut persist_undo = self.file.persist_undo();
+                ui.checkbox(&mut persist_undo, "Save history in file")
+                    .on_hover_text("Undo history is encrypted with file contents");
+                self.file.set_persist_undo(persist_undo);
+
+                ui.separator();
+
+                let (edits, applied) = self.file.undo_history().edits();
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        if ui.selectable_label(applied == 0, "Original").clicked() {
+                            jump_to = Some(0);
+                        }
+
+                        for (index, edit) in edits.iter().enumerate() {
+                            // Undone edits are shown faded
+                            let mut text = egui::RichText::new(edit.describe());
+                            if index >= applied {
+                                text = text.weak();
+                            }
+
+                            if ui.selectable_label(index + 1 == applied, text).clicked() {
+                                jump_to = Some(index + 1);
+                            }
+                        }
+                    });
+            });
+
+        self.show_undo_history = open;
+
+        if let Some(applied) = jump_to {
+            if let Some(range) = self.file.undo_to(applied) {
+                self.select_in_editor(ctx, range);
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use chrono::Duration;
+use eframe::egui;
+
+use super::{
+    diff::{diff_mode_buttons, Diff, DiffMode},
+    App,
+};
+use crate::versions::Version;
+
+/// State of version history window
+pub struct VersionsPanel {
+    /// Whether window is open
+    open: bool,
+    /// Index of version to compare with current text
+    selected: Option<usize>,
+    /// Number of versions to keep when pruning by count
+    keep_count: usize,
+    /// Age in days of versions to keep when pruning by age
+    keep_days: i64,
+    /// Whether to compare lines or words
+    diff_mode: DiffMode,
+    /// Text of selected version, and its changes to current text
+    selected_cache: Option<SelectedVersion>,
+}
+
+/// Text of selected version, and its changes to current text
+///
+/// Kept between frames, as decompressing and comparing is slow for large files
+struct SelectedVersion {
+    /// Version which text was read from
+    version: Version,
+    /// Text of version
+    ///
+    /// `None` if it could not be read
+    text: Option<String>,
+    /// Changes since version, with the current text and mode they were found for
+    diff: Option<(String, DiffMode, Diff)>,
+}
+
+impl Default for VersionsPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            selected: None,
+            keep_count: 20,
+            keep_days: 30,
+            diff_mode: DiffMode::default(),
+            selected_cache: None,
+        }
+    }
+}
+
+impl VersionsPanel {
+    /// Open window, or close if open
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+}
+
+impl App {
+    /// Render version history window, if open
+    ///
+    /// Restoring a version replaces the text as an unsaved edit, which can be undone
+    pub(super) fn render_versions(&mut self, ctx: &egui::Context) {
+        // Do not show versions while locked
+        if self.locked {
+            return;
+        }
+
+        let mut open = self.versions_panel.open;
+        let mut restore = None;
+        let mut delete = None;
+
+        egui::Window::new("Version history")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                let panel = &mut self.versions_panel;
+                let editable = !self.file.is_read_only();
+                // Versions are not changed while saving, as saved file replaces them
+                let versions_editable = editable && !*self.writing.lock().unwrap();
+
+                let mut keep_versions = self.file.keep_versions();
+                ui.add_enabled(
+                    editable,
+                    egui::Checkbox::new(&mut keep_versions, "Save a version on every save"),
+                )
+                .on_hover_text("Versions are encrypted with file contents");
+                self.file.set_keep_versions(keep_versions);
+
+                // * Prune
+
+                let mut pruned = 0;
+                ui.add_enabled_ui(versions_editable, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Keep latest");
+                        ui.add(egui::DragValue::new(&mut panel.keep_count).clamp_range(1..=1000));
+                        if ui.button("Prune").clicked() {
+                            pruned = self.file.versions_mut().keep_latest(panel.keep_count);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Remove older than");
+                        ui.add(
+                            egui::DragValue::new(&mut panel.keep_days)
+                                .clamp_range(1..=3650)
+                                .suffix(" days"),
+                        );
+                        if ui.button("Prune").clicked() {
+                            pruned = self
+                                .file
+                                .versions_mut()
+                                .remove_older_than(Duration::days(panel.keep_days));
+                        }
+                    });
+                });
+                if pruned > 0 {
+                    println!("Pruned {} versions", pruned);
+                    panel.selected = None;
+                    self.file.mark_as_unsaved();
+                }
+
+                ui.separator();
+
+                // * List of versions, newest first
+
+                let versions = self.file.versions().versions();
+                if versions.is_empty() {
+                    ui.weak("No saved versions");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_source("versions_list")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for (index, version) in versions.iter().enumerate().rev() {
+                            let time = version.time().format("%Y-%m-%d %H:%M:%S").to_string();
+                            if ui
+                                .selectable_label(panel.selected == Some(index), time)
+                                .clicked()
+                            {
+                                panel.selected = Some(index);
+                            }
+                        }
+                    });
+
+                // * Compare selected version with current text
+
+                let Some(version) = panel.selected.and_then(|index| versions.get(index)) else {
+                    return;
+                };
+
+                ui.separator();
+
+                // Read text again only if another version was selected
+                if panel
+                    .selected_cache
+                    .as_ref()
+                    .is_none_or(|cache| cache.version != *version)
+                {
+                    panel.selected_cache = Some(SelectedVersion {
+                        version: version.clone(),
+                        text: version
+                            .text()
+                            .map_err(|error| eprintln!("Failed to read version: {:?}", error))
+                            .ok(),
+                        diff: None,
+                    });
+                }
+                let Some(cache) = &mut panel.selected_cache else {
+                    return;
+                };
+                let Some(text) = &cache.text else {
+                    ui.colored_label(ui.visuals().error_fg_color, "Version could not be read");
+                    return;
+                };
+
+                ui.horizontal(|ui| {
+                    ui.strong("Changes since this version");
+                    if ui
+                        .add_enabled(editable, egui::Button::new("Restore"))
+                        .clicked()
+                    {
+                        restore = Some(text.clone());
+                    }
+                    if ui
+                        .add_enabled(versions_editable, egui::Button::new("Delete"))
+                        .clicked()
+                    {
+                        delete = panel.selected.take();
+                    }
+                    diff_mode_buttons(ui, &mut panel.diff_mode);
+                });
+
+                // Compare again only if text or mode changed
+                let contents = self.file.contents();
+                let changed = !matches!(
+                    &cache.diff,
+                    Some((compared, mode, _)) if compared == contents && *mode == panel.diff_mode
+                );
+                if changed {
+                    let diff = Diff::new(text, contents, panel.diff_mode);
+                    cache.diff = Some((contents.clone(), panel.diff_mode, diff));
+                }
+                if let Some((_, _, diff)) = &cache.diff {
+                    diff.show(ui, "versions_diff");
+                }
+            });
+
+        self.versions_panel.open = open;
+
+        if let Some(index) = delete {
+            self.file.versions_mut().remove(index);
+            self.file.mark_as_unsaved();
+        }
+
+        // Restore as a single edit, so it can be undone
+        if let Some(text) = restore {
+            *self.file.contents_mut() = text;
+            self.register_edit();
+        }
+    }
+}
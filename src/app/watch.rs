@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use eframe::egui;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{methods::display_crypto_error, App, ConcurrentMessage};
+use crate::{File, KEY};
+
+/// Watches directory of open file, for changes by other programs
+///
+/// Directory is watched instead of file, as some programs replace the file instead of writing to it
+pub struct FileWatcher {
+    /// Path of file being watched
+    path: String,
+    /// `None` if watcher could not be created
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl App {
+    /// Watch path of current file, if it changed since last frame
+    pub(super) fn update_watcher(&mut self, ctx: &egui::Context) {
+        let path = self.file.path();
+        if self.watcher.as_ref().map(|watcher| &watcher.path) == path {
+            return;
+        }
+
+        self.watcher = path.map(|path| {
+            let watcher = watch_file(path, self.channel.sender.clone(), ctx.clone())
+                .map_err(|error| eprintln!("Failed to watch file: {:?}", error))
+                .ok();
+
+            FileWatcher {
+                path: path.clone(),
+                _watcher: watcher,
+            }
+        });
+    }
+
+    /// Check if file was changed by another program, after watcher noticed a change
+    ///
+    /// Waits until file is not writing, as this program also changes the file when saving
+    pub(super) fn check_disk_changes(&mut self) {
+        if !self.disk_check_pending || *self.writing.lock().unwrap() {
+            return;
+        }
+        self.disk_check_pending = false;
+
+        if self.file.is_changed_on_disk() {
+            println!("File changed on disk");
+            self.external_change = true;
+        }
+    }
+
+    /// Open file again from disk, discarding any changes
+    pub(super) fn reload_file(&mut self) {
+        let Some(path) = self.file.path().cloned() else {
+            return;
+        };
+
+        let key = self
+            .passphrase_for(Some(&path), self.file.container())
+            .unwrap_or_else(|| KEY.to_string());
+        match File::open_path_and_decrypt(path, &key) {
+            Ok(mut file) => {
+                // Keep read-only mode, unless file is no longer writable
+                if file.read_only().is_none() {
+                    file.set_read_only(self.file.read_only());
+                }
+                self.file = file;
+            }
+            Err(error) => self.set_error_message(display_crypto_error(error)),
+        }
+    }
+}
+
+/// Watch directory of file, sending a message when the file may have changed
+fn watch_file(
+    path: &str,
+    sender: std::sync::mpsc::Sender<ConcurrentMessage>,
+    ctx: egui::Context,
+) -> notify::Result<RecommendedWatcher> {
+    let path = Path::new(path);
+    let file_path = path.to_path_buf();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) || !event.paths.contains(&file_path) {
+            return;
+        }
+
+        // Receiver is dropped when program closes
+        if sender.send(ConcurrentMessage::ChangedOnDisk).is_ok() {
+            ctx.request_repaint();
+        }
+    })?;
+
+    let dir = path.parent().unwrap_or(path);
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    Ok(watcher)
+}
@@ -0,0 +1,129 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{file, Settings, KEY};
+
+/// Usage of command line interface
+const USAGE: &str = "\
+Usage:
+    encrypted-text-editor                          Open editor
+    encrypted-text-editor encrypt <INPUT> <OUTPUT>  Encrypt file, in stream format, with default cipher
+    encrypted-text-editor decrypt <INPUT> <OUTPUT>  Decrypt file, and write its contents
+    encrypted-text-editor migrate <DIRECTORY> (--password | --recipient <KEY>...) [--no-backup]
+                                                   Re-encrypt files which use the legacy key, as age files
+                                                   Password is read from standard input";
+
+/// Run command from command line arguments, excluding program name
+///
+/// Returns exit code of program
+pub fn run_cli(args: &[String]) -> i32 {
+    let result = match args {
+        [command, input, output] if command == "encrypt" => {
+            file::encrypt_file(input, output, KEY, Settings::load().default_cipher)
+        }
+        [command, input, output] if command == "decrypt" => file::decrypt_file(input, output, KEY),
+        [command, directory, options @ ..] if command == "migrate" => {
+            return migrate(directory, options);
+        }
+        [command] if command == "help" || command == "--help" || command == "-h" => {
+            println!("{USAGE}");
+            return 0;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return 2;
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("Error: {:?}", error);
+            1
+        }
+    }
+}
+
+/// Migrate directory from legacy key, and write report
+///
+/// Returns exit code of program, which is `1` if any file failed
+fn migrate(directory: &str, options: &[String]) -> i32 {
+    let mut password = false;
+    let mut recipients = Vec::new();
+    let mut backup = true;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--password" => password = true,
+            "--recipient" => match options.next() {
+                Some(recipient) => recipients.push(recipient.as_str()),
+                None => {
+                    eprintln!("{USAGE}");
+                    return 2;
+                }
+            },
+            "--no-backup" => backup = false,
+            _ => {
+                eprintln!("{USAGE}");
+                return 2;
+            }
+        }
+    }
+
+    let target = match (password, recipients.is_empty()) {
+        (true, true) => match read_password() {
+            Ok(password) => file::MigrationTarget::Password(password),
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return 1;
+            }
+        },
+        (false, false) => match file::MigrationTarget::recipients(&recipients.join("\n"), false) {
+            Ok(target) => target,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return 2;
+            }
+        },
+        // Exactly one of password or recipients must be given
+        _ => {
+            eprintln!("{USAGE}");
+            return 2;
+        }
+    };
+
+    let extension = Settings::load().file_extension;
+    let report = match file::migrate_directory(directory, &extension, KEY, &target, backup) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return 1;
+        }
+    };
+
+    print!("{}", report.to_text());
+    match report.write() {
+        Ok(path) => println!("\nReport written to {}", path),
+        Err(error) => eprintln!("Failed to write report: {}", error),
+    }
+
+    i32::from(!report.failed.is_empty())
+}
+
+/// Read password from first line of standard input
+fn read_password() -> io::Result<String> {
+    eprint!("Password: ");
+    io::stderr().flush()?;
+
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+
+    if password.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Password is empty",
+        ));
+    }
+    Ok(password)
+}
@@ -0,0 +1,372 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeMap;
+
+use eframe::egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Named action which can be run with a button or keybind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Command {
+    Save,
+    SaveAs,
+    SaveAsAge,
+    Open,
+    New,
+    Import,
+    Export,
+    CopyArmored,
+    PasteArmored,
+    Migrate,
+    ShowChanges,
+    ReadOnly,
+    Undo,
+    Redo,
+    UndoHistory,
+    Find,
+    Replace,
+    Preview,
+    Versions,
+    Attachments,
+    Properties,
+    Lock,
+    Preferences,
+    Palette,
+}
+
+impl Command {
+    /// Every command, in order of display
+    pub const ALL: &'static [Self] = &[
+        Self::Save,
+        Self::SaveAs,
+        Self::SaveAsAge,
+        Self::Open,
+        Self::New,
+        Self::Import,
+        Self::Export,
+        Self::CopyArmored,
+        Self::PasteArmored,
+        Self::Migrate,
+        Self::ShowChanges,
+        Self::ReadOnly,
+        Self::Undo,
+        Self::Redo,
+        Self::UndoHistory,
+        Self::Find,
+        Self::Replace,
+        Self::Preview,
+        Self::Versions,
+        Self::Attachments,
+        Self::Properties,
+        Self::Lock,
+        Self::Preferences,
+        Self::Palette,
+    ];
+
+    /// Name used in config file
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Save => "save",
+            Self::SaveAs => "save_as",
+            Self::SaveAsAge => "save_as_age",
+            Self::Open => "open",
+            Self::New => "new",
+            Self::Import => "import",
+            Self::Export => "export",
+            Self::CopyArmored => "copy_armored",
+            Self::PasteArmored => "paste_armored",
+            Self::Migrate => "migrate",
+            Self::ShowChanges => "show_changes",
+            Self::ReadOnly => "read_only",
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+            Self::UndoHistory => "undo_history",
+            Self::Find => "find",
+            Self::Replace => "replace",
+            Self::Preview => "preview",
+            Self::Versions => "versions",
+            Self::Attachments => "attachments",
+            Self::Properties => "properties",
+            Self::Lock => "lock",
+            Self::Preferences => "preferences",
+            Self::Palette => "command_palette",
+        }
+    }
+
+    /// Human-readable name, used for buttons and command palette
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Save => "Save",
+            Self::SaveAs => "Save As",
+            Self::SaveAsAge => "Save As age...",
+            Self::Open => "Open",
+            Self::New => "New",
+            Self::Import => "Import Plaintext...",
+            Self::Export => "Export Decrypted...",
+            Self::CopyArmored => "Copy as Armored Text",
+            Self::PasteArmored => "Open Armored Text from Clipboard",
+            Self::Migrate => "Migrate Legacy Files...",
+            Self::ShowChanges => "Show Changes",
+            Self::ReadOnly => "Read-only Mode",
+            Self::Undo => "Undo",
+            Self::Redo => "Redo",
+            Self::UndoHistory => "Undo History",
+            Self::Find => "Find",
+            Self::Replace => "Replace",
+            Self::Preview => "Markdown Preview",
+            Self::Versions => "Version History",
+            Self::Attachments => "Attachments",
+            Self::Properties => "Document Properties",
+            Self::Lock => "Lock",
+            Self::Preferences => "Preferences",
+            Self::Palette => "Command Palette",
+        }
+    }
+
+    /// Keybind used if not changed by user
+    pub fn default_keybind(self) -> Option<KeyboardShortcut> {
+        let shortcut = |modifiers, key| Some(KeyboardShortcut::new(modifiers, key));
+
+        match self {
+            Self::Save => shortcut(Modifiers::CTRL, Key::S),
+            Self::SaveAs => shortcut(Modifiers::CTRL | Modifiers::SHIFT, Key::S),
+            Self::SaveAsAge => None,
+            Self::Open => shortcut(Modifiers::CTRL, Key::O),
+            Self::New => shortcut(Modifiers::CTRL, Key::N),
+            Self::Import => None,
+            Self::Export => None,
+            Self::CopyArmored => None,
+            Self::PasteArmored => None,
+            Self::Migrate => None,
+            Self::ShowChanges => None,
+            Self::ReadOnly => None,
+            Self::Undo => shortcut(Modifiers::CTRL, Key::Z),
+            Self::Redo => shortcut(Modifiers::CTRL, Key::Y),
+            Self::UndoHistory => None,
+            Self::Find => shortcut(Modifiers::CTRL, Key::F),
+            Self::Replace => shortcut(Modifiers::CTRL, Key::H),
+            Self::Preview => shortcut(Modifiers::CTRL | Modifiers::SHIFT, Key::M),
+            Self::Versions => None,
+            Self::Attachments => None,
+            Self::Properties => None,
+            Self::Lock => shortcut(Modifiers::CTRL, Key::L),
+            Self::Preferences => None,
+            Self::Palette => shortcut(Modifiers::CTRL | Modifiers::SHIFT, Key::P),
+        }
+    }
+}
+
+/// Keybind for every command, which may be changed by user
+///
+/// Serialized as a table of command ids to keybinds, such as `save = "Ctrl+S"`
+///
+/// Unbound commands are serialized as an empty string
+#[derive(Clone, PartialEq)]
+pub struct Keybinds {
+    binds: BTreeMap<Command, Option<KeyboardShortcut>>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            binds: Command::ALL
+                .iter()
+                .map(|&command| (command, command.default_keybind()))
+                .collect(),
+        }
+    }
+}
+
+impl Keybinds {
+    /// Get keybind of command
+    ///
+    /// `None` if command is unbound
+    pub fn get(&self, command: Command) -> Option<KeyboardShortcut> {
+        self.binds.get(&command).copied().flatten()
+    }
+
+    /// Set keybind of command
+    ///
+    /// `None` to unbind command
+    pub fn set(&mut self, command: Command, keybind: Option<KeyboardShortcut>) {
+        self.binds.insert(command, keybind);
+    }
+
+    /// Get other commands which have the same keybind as given command
+    pub fn conflicts(&self, command: Command) -> Vec<Command> {
+        let Some(keybind) = self.get(command) else {
+            return Vec::new();
+        };
+
+        Command::ALL
+            .iter()
+            .copied()
+            .filter(|&other| other != command && self.get(other) == Some(keybind))
+            .collect()
+    }
+}
+
+impl Serialize for Keybinds {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.binds
+            .iter()
+            .map(|(command, keybind)| {
+                let text = keybind.as_ref().map(format_keybind).unwrap_or_default();
+                (command.id(), text)
+            })
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Keybinds {
+    /// Unknown commands and invalid keybinds are ignored, using the default keybind
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = BTreeMap::<String, String>::deserialize(deserializer)?;
+        let mut keybinds = Self::default();
+
+        for (id, text) in table {
+            let Some(&command) = Command::ALL.iter().find(|command| command.id() == id) else {
+                eprintln!("Unknown command in keybinds: {:?}", id);
+                continue;
+            };
+
+            if text.is_empty() {
+                keybinds.set(command, None);
+                continue;
+            }
+
+            match parse_keybind(&text) {
+                Some(keybind) => keybinds.set(command, Some(keybind)),
+                None => eprintln!("Invalid keybind for {:?}: {:?}", id, text),
+            }
+        }
+
+        Ok(keybinds)
+    }
+}
+
+/// Convert keybind to text, such as `Ctrl+Shift+S`
+///
+/// This is used in config file, and is the same on all platforms
+pub fn format_keybind(keybind: &KeyboardShortcut) -> String {
+    let mut text = String::new();
+
+    if keybind.modifiers.ctrl {
+        text += "Ctrl+";
+    }
+    if keybind.modifiers.alt {
+        text += "Alt+";
+    }
+    if keybind.modifiers.shift {
+        text += "Shift+";
+    }
+
+    text + keybind.key.name()
+}
+
+/// Parse keybind from text, such as `Ctrl+Shift+S`
+///
+/// Returns `None` if text is invalid
+pub fn parse_keybind(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+
+    let key_name = parts.pop()?;
+    let key = KEYS
+        .iter()
+        .copied()
+        .find(|key| key.name().eq_ignore_ascii_case(key_name))?;
+
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" => modifiers.ctrl = true,
+            "alt" => modifiers.alt = true,
+            "shift" => modifiers.shift = true,
+            _ => return None,
+        }
+    }
+
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+/// Create keybind from a key press, ignoring platform-specific modifiers
+pub fn keybind_from_press(modifiers: Modifiers, key: Key) -> KeyboardShortcut {
+    let modifiers = Modifiers {
+        ctrl: modifiers.ctrl,
+        alt: modifiers.alt,
+        shift: modifiers.shift,
+        ..Modifiers::NONE
+    };
+    KeyboardShortcut::new(modifiers, key)
+}
+
+/// Every key which can be used in a keybind
+const KEYS: &[Key] = &[
+    Key::ArrowDown,
+    Key::ArrowLeft,
+    Key::ArrowRight,
+    Key::ArrowUp,
+    Key::Escape,
+    Key::Tab,
+    Key::Backspace,
+    Key::Enter,
+    Key::Space,
+    Key::Insert,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Minus,
+    Key::PlusEquals,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+];
@@ -0,0 +1,91 @@
+use super::*;
+
+#[test]
+fn keybind_round_trip() {
+    let modifiers = [
+        Modifiers::NONE,
+        Modifiers::CTRL,
+        Modifiers::ALT,
+        Modifiers::SHIFT,
+        Modifiers::CTRL | Modifiers::SHIFT,
+        Modifiers::CTRL | Modifiers::ALT | Modifiers::SHIFT,
+    ];
+
+    for &key in KEYS {
+        for modifiers in modifiers {
+            let keybind = KeyboardShortcut::new(modifiers, key);
+            let text = format_keybind(&keybind);
+            assert_eq!(parse_keybind(&text), Some(keybind), "{}", text);
+        }
+    }
+}
+
+#[test]
+fn format_keybinds() {
+    assert_eq!(
+        format_keybind(&KeyboardShortcut::new(
+            Modifiers::CTRL | Modifiers::SHIFT,
+            Key::S
+        )),
+        "Ctrl+Shift+S"
+    );
+    assert_eq!(
+        format_keybind(&KeyboardShortcut::new(Modifiers::CTRL, Key::PlusEquals)),
+        "Ctrl+Plus"
+    );
+    assert_eq!(
+        format_keybind(&KeyboardShortcut::new(Modifiers::NONE, Key::F1)),
+        "F1"
+    );
+}
+
+#[test]
+fn parse_keybinds() {
+    // Case and spaces are ignored
+    assert_eq!(
+        parse_keybind("ctrl + shift + s"),
+        Some(KeyboardShortcut::new(
+            Modifiers::CTRL | Modifiers::SHIFT,
+            Key::S
+        ))
+    );
+    // Order of modifiers is ignored
+    assert_eq!(parse_keybind("Shift+Ctrl+S"), parse_keybind("Ctrl+Shift+S"));
+}
+
+#[test]
+fn parse_invalid_keybinds() {
+    for text in [
+        "",
+        "+",
+        "Ctrl+",
+        "Ctrl",
+        "Ctrl+Ctrl",
+        "Meta+S",
+        "Ctrl+Unknown",
+        "Ctrl+S+",
+        "Ctrl++S",
+    ] {
+        assert_eq!(parse_keybind(text), None, "{:?}", text);
+    }
+}
+
+#[test]
+fn keybinds_round_trip() {
+    let mut keybinds = Keybinds::default();
+    keybinds.set(Command::Save, None);
+    keybinds.set(
+        Command::Open,
+        Some(KeyboardShortcut::new(Modifiers::ALT, Key::O)),
+    );
+
+    let text = toml::to_string(&keybinds).unwrap();
+    assert!(text.contains("\"Alt+O\""));
+    assert!(toml::from_str::<Keybinds>(&text).unwrap() == keybinds);
+
+    // Invalid keybinds use default
+    let text = text.replace("\"Alt+O\"", "\"Ctrl+Unknown\"");
+    let loaded: Keybinds = toml::from_str(&text).unwrap();
+    assert_eq!(loaded.get(Command::Open), Command::Open.default_keybind());
+    assert_eq!(loaded.get(Command::Save), None);
+}
@@ -0,0 +1,172 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    iter,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use ::age::{
+    secrecy::ExposeSecret, secrecy::SecretString, x25519, DecryptError, Decryptor, Encryptor,
+};
+
+use super::permissions;
+use crate::get_config_dir;
+
+/// Start of age file
+const MAGIC: &[u8] = b"age-encryption.org/v1\n";
+
+/// Start of header line for passphrase
+const SCRYPT_STANZA: &[u8] = b"\n-> scrypt ";
+
+/// Name of identity file, in config directory
+const IDENTITY_FILE: &str = "age-identity.txt";
+
+/// Key to encrypt or decrypt age file
+pub enum AgeKey<'a> {
+    /// Passphrase, with scrypt
+    Passphrase(&'a str),
+    /// Identity, with X25519
+    ///
+    /// Files are encrypted to recipient of identity
+    Identity(&'a x25519::Identity),
+    /// Public keys of other users, with X25519
+    ///
+    /// Only used to encrypt, as files cannot be decrypted without an identity
+    Recipients(&'a [x25519::Recipient]),
+}
+
+/// Returns `true` if bytes are the start of an age file
+pub fn is_age(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Returns `true` if header of age file is for a passphrase, instead of recipients
+pub fn is_passphrase(header: &[u8]) -> bool {
+    header
+        .windows(SCRYPT_STANZA.len())
+        .any(|window| window == SCRYPT_STANZA)
+}
+
+/// Encrypt from reader to writer, as age file
+pub fn encrypt(
+    mut reader: impl Read,
+    writer: impl Write,
+    key: AgeKey,
+) -> Result<(), cocoon::Error> {
+    let encryptor = match key {
+        AgeKey::Passphrase(passphrase) => {
+            Encryptor::with_user_passphrase(SecretString::new(passphrase.to_string()))
+        }
+        AgeKey::Identity(identity) => {
+            Encryptor::with_recipients(vec![Box::new(identity.to_public())])
+                .expect("Recipients should not be empty")
+        }
+        AgeKey::Recipients(recipients) => Encryptor::with_recipients(
+            recipients
+                .iter()
+                .map(|recipient| Box::new(recipient.clone()) as Box<dyn ::age::Recipient + Send>)
+                .collect(),
+        )
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No recipients"))?,
+    };
+
+    let mut writer = encryptor.wrap_output(writer).map_err(io::Error::other)?;
+    io::copy(&mut reader, &mut writer)?;
+    writer.finish()?.flush()?;
+    Ok(())
+}
+
+/// Decrypt from reader to writer, from age file
+///
+/// Key must be the same type as used to encrypt
+pub fn decrypt(
+    reader: impl Read,
+    mut writer: impl Write,
+    key: AgeKey,
+) -> Result<(), cocoon::Error> {
+    let mut reader = match (Decryptor::new(reader).map_err(decrypt_error)?, key) {
+        (Decryptor::Passphrase(decryptor), AgeKey::Passphrase(passphrase)) => decryptor
+            .decrypt(&SecretString::new(passphrase.to_string()), None)
+            .map_err(decrypt_error)?,
+        (Decryptor::Recipients(decryptor), AgeKey::Identity(identity)) => decryptor
+            .decrypt(iter::once(identity as &dyn ::age::Identity))
+            .map_err(decrypt_error)?,
+        _ => return Err(cocoon::Error::Cryptography),
+    };
+
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Convert error to match other formats
+fn decrypt_error(error: DecryptError) -> cocoon::Error {
+    match error {
+        DecryptError::Io(error) => cocoon::Error::Io(error),
+        DecryptError::InvalidHeader | DecryptError::UnknownFormat => {
+            cocoon::Error::UnrecognizedFormat
+        }
+        DecryptError::ExcessiveWork { .. } => cocoon::Error::Io(io::Error::other(error)),
+        _ => cocoon::Error::Cryptography,
+    }
+}
+
+/// Get path of identity file, in config directory
+pub fn identity_path() -> Option<PathBuf> {
+    get_config_dir().map(|dir| dir.join(IDENTITY_FILE))
+}
+
+/// Read identity from identity file, creating it if it does not exist
+///
+/// File has the same format as from `age-keygen`, so it can be used with `age -d -i`
+pub fn identity() -> io::Result<x25519::Identity> {
+    let path = identity_path().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "Could not find config directory")
+    })?;
+
+    if !path.exists() {
+        let identity = x25519::Identity::generate();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write!(
+            permissions::create(&path.display().to_string())?,
+            "# public key: {}\n{}\n",
+            identity.to_public(),
+            identity.to_string().expose_secret(),
+        )?;
+        return Ok(identity);
+    }
+
+    parse_identity(&fs::read_to_string(path)?)
+}
+
+/// Read public keys of recipients, such as `age1...`
+///
+/// Keys are separated by whitespace or lines, and comments are ignored
+pub fn parse_recipients(text: &str) -> io::Result<Vec<x25519::Recipient>> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace)
+        .map(|key| {
+            x25519::Recipient::from_str(key).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid recipient: {}", key),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Read identity from text of identity file
+///
+/// Comments and empty lines are ignored
+pub fn parse_identity(text: &str) -> io::Result<x25519::Identity> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .and_then(|line| x25519::Identity::from_str(line).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid identity file"))
+}
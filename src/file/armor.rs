@@ -0,0 +1,63 @@
+use std::io;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// First line of armored text
+const BEGIN: &str = "-----BEGIN ENCRYPTED TEXT-----";
+
+/// Last line of armored text
+const END: &str = "-----END ENCRYPTED TEXT-----";
+
+/// Length of lines of base64
+const LINE_LENGTH: usize = 64;
+
+/// Returns `true` if bytes are the start of armored text
+///
+/// Leading whitespace is allowed, as text may be pasted
+pub fn is_armored(bytes: &[u8]) -> bool {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    bytes[start..].starts_with(BEGIN.as_bytes())
+}
+
+/// Convert encrypted bytes to armored text
+///
+/// Text is base64 between markers, wrapped to lines, so it can be sent over text-only channels
+pub fn armor(bytes: &[u8]) -> String {
+    let encoded = STANDARD.encode(bytes);
+
+    let mut text = String::from(BEGIN);
+    text.push('\n');
+    // Base64 is ASCII, so it can be split at any byte
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        text.push_str(&String::from_utf8_lossy(line));
+        text.push('\n');
+    }
+    text.push_str(END);
+    text.push('\n');
+    text
+}
+
+/// Convert armored text to encrypted bytes
+///
+/// Text outside markers and whitespace inside markers is ignored, such as from email clients
+pub fn dearmor(text: &str) -> io::Result<Vec<u8>> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let (_, rest) = text
+        .split_once(BEGIN)
+        .ok_or_else(|| invalid("Missing start of armored text"))?;
+    let (encoded, _) = rest
+        .split_once(END)
+        .ok_or_else(|| invalid("Missing end of armored text"))?;
+
+    let encoded: String = encoded
+        .chars()
+        .filter(|char| !char.is_whitespace())
+        .collect();
+    STANDARD
+        .decode(encoded)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
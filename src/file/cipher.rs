@@ -0,0 +1,49 @@
+use cocoon::CocoonCipher;
+use serde::{Deserialize, Serialize};
+
+/// Position of cipher in header of `cocoon` container
+///
+/// After magic number and version
+const COCOON_CIPHER_INDEX: usize = 4;
+
+/// Algorithm to encrypt file with, for formats which support a choice
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Cipher {
+    /// ChaCha20-Poly1305, fast without hardware support
+    #[default]
+    ChaCha20Poly1305,
+    /// AES-256-GCM, often required by compliance rules
+    Aes256Gcm,
+}
+
+impl Cipher {
+    /// All ciphers, in order shown to user
+    pub const ALL: [Self; 2] = [Self::ChaCha20Poly1305, Self::Aes256Gcm];
+
+    /// Human-readable name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ChaCha20Poly1305 => "ChaCha20-Poly1305",
+            Self::Aes256Gcm => "AES-256-GCM",
+        }
+    }
+
+    /// Same cipher, as used by `cocoon`
+    pub fn to_cocoon(self) -> CocoonCipher {
+        match self {
+            Self::ChaCha20Poly1305 => CocoonCipher::Chacha20Poly1305,
+            Self::Aes256Gcm => CocoonCipher::Aes256Gcm,
+        }
+    }
+
+    /// Read cipher from header of `cocoon` container
+    ///
+    /// `None` if header is too short, or cipher is unknown
+    pub fn from_cocoon_header(header: &[u8]) -> Option<Self> {
+        match header.get(COCOON_CIPHER_INDEX)? {
+            1 => Some(Self::ChaCha20Poly1305),
+            2 => Some(Self::Aes256Gcm),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::Hasher,
+    io::{self, Read, Write},
+    time::SystemTime,
+};
+
+/// State of encrypted file on disk, when it was last opened or saved
+///
+/// Used to detect changes by other programs
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskState {
+    /// Time file was last modified
+    ///
+    /// `None` if not supported by platform
+    modified: Option<SystemTime>,
+    /// Hash of encrypted bytes
+    hash: u64,
+}
+
+impl DiskState {
+    /// Get state from hash of bytes which were read or written, and metadata of file
+    pub fn new(hash: u64, metadata: &fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            hash,
+        }
+    }
+
+    /// Get state of file at path
+    pub fn read(path: &str) -> io::Result<Self> {
+        let mut reader = Hashing::new(fs::File::open(path)?);
+        io::copy(&mut reader, &mut io::sink())?;
+        Ok(Self::new(reader.finish(), &fs::metadata(path)?))
+    }
+
+    /// Returns `true` if file at path is different to this state
+    ///
+    /// Hash is only compared if modification time changed, as a file may be written without changing
+    ///
+    /// Missing or unreadable files are not counted as changed, as they can be saved again
+    pub fn is_changed(&self, path: &str) -> bool {
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+        if metadata.modified().ok() == self.modified {
+            return false;
+        }
+
+        match Self::read(path) {
+            Ok(state) => state.hash != self.hash,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Hashes all bytes which are read or written through it
+///
+/// Used to hash a file while it is read or written, without keeping a copy
+pub struct Hashing<T> {
+    inner: T,
+    hasher: DefaultHasher,
+}
+
+impl<T> Hashing<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: DefaultHasher::new(),
+        }
+    }
+
+    /// Get inner reader or writer, without hashing
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Get hash of all bytes so far
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+}
+
+impl<R: Read> Read for Hashing<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.hasher.write(&buf[..count]);
+        Ok(count)
+    }
+}
+
+impl<W: Write> Write for Hashing<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.inner.write(buf)?;
+        self.hasher.write(&buf[..count]);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::{self, Read, Write},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use serde::{Deserialize, Serialize};
+
+use super::padding::{self, Padding};
+use crate::{highlight::Language, undo::UndoHistory, versions::VersionHistory};
+
+/// Start of decrypted data, for files with metadata
+///
+/// Files without this are plain text, from before metadata was added
+///
+/// First byte is never valid in UTF-8, so cannot be confused with plain text
+const MAGIC: &[u8] = b"\xFFETED";
+
+/// Start of decrypted data, for files with metadata which are compressed
+///
+/// Followed by the whole data with `MAGIC`, compressed with deflate
+const COMPRESSED_MAGIC: &[u8] = b"\xFFETEZ";
+
+/// Version of format, after magic bytes
+const VERSION: u8 = 1;
+
+/// Largest size of decompressed data, in bytes
+///
+/// Larger data is refused, so a small file cannot fill memory when opened
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Information about file, stored encrypted with contents
+///
+/// Missing values use defaults, so files from older versions can still be opened
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    /// Language of contents, for syntax highlighting
+    pub language: Language,
+    /// Whether undo history is saved in file
+    pub persist_undo: bool,
+    /// Whether a version is added to version history on every save
+    pub keep_versions: bool,
+    /// Whether data is compressed before encryption
+    pub compress: bool,
+    /// How data is padded before encryption
+    pub padding: Padding,
+}
+
+/// Named binary file, stored encrypted with contents
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Name of file, including extension
+    pub name: String,
+    /// Bytes of file
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+    /// Hash of data, computed once, so attachment can be identified without hashing data again
+    ///
+    /// Not saved, as it is computed when file is opened
+    #[serde(skip)]
+    hash: u64,
+}
+
+impl Attachment {
+    /// Create attachment, computing hash of data
+    pub fn new(name: String, data: Vec<u8>) -> Self {
+        let mut attachment = Self {
+            name,
+            data,
+            hash: 0,
+        };
+        attachment.update_hash();
+        attachment
+    }
+
+    /// Get hash of data
+    ///
+    /// Equal for attachments with the same data
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Compute hash of data
+    fn update_hash(&mut self) {
+        let mut hasher = DefaultHasher::new();
+        self.data.hash(&mut hasher);
+        self.hash = hasher.finish();
+    }
+}
+
+/// Contents and metadata, to serialize without cloning
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    contents: &'a str,
+    metadata: &'a Metadata,
+    undo: Option<&'a UndoHistory>,
+    versions: &'a VersionHistory,
+    attachments: &'a [Attachment],
+}
+
+/// Contents and metadata, read from file
+///
+/// Must have same fields as `DocumentRef`
+#[derive(Deserialize)]
+pub struct Document {
+    pub contents: String,
+    pub metadata: Metadata,
+    /// Undo history, if it was saved
+    #[serde(default)]
+    pub undo: Option<UndoHistory>,
+    /// Text of file at previous saves
+    #[serde(default)]
+    pub versions: VersionHistory,
+    /// Binary files stored with contents
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+/// Convert contents and metadata to bytes, to be encrypted
+///
+/// Undo history is only included if given
+///
+/// Bytes are compressed, if enabled in metadata
+pub fn encode(
+    contents: &str,
+    metadata: &Metadata,
+    undo: Option<&UndoHistory>,
+    versions: &VersionHistory,
+    attachments: &[Attachment],
+) -> io::Result<Vec<u8>> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+
+    let document = DocumentRef {
+        contents,
+        metadata,
+        undo,
+        versions,
+        attachments,
+    };
+    rmp_serde::encode::write_named(&mut bytes, &document)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    if !metadata.compress {
+        return Ok(bytes);
+    }
+
+    let mut encoder = DeflateEncoder::new(COMPRESSED_MAGIC.to_vec(), Compression::default());
+    encoder.write_all(&bytes)?;
+    encoder.finish()
+}
+
+/// Convert decrypted bytes to contents and metadata
+///
+/// Bytes without magic are read as plain text, with default metadata
+///
+/// Compressed bytes are decompressed, up to `MAX_DECOMPRESSED_SIZE`
+pub fn decode(bytes: Vec<u8>) -> io::Result<Document> {
+    let bytes = match bytes.strip_prefix(COMPRESSED_MAGIC) {
+        Some(compressed) => decompress(compressed, MAX_DECOMPRESSED_SIZE)?,
+        None => bytes,
+    };
+
+    let Some(rest) = bytes.strip_prefix(MAGIC) else {
+        // Convert bytes to string
+        // This may fail, if bytes do not form a valid utf8 string
+        let contents = String::from_utf8(bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        return Ok(Document {
+            contents,
+            metadata: Metadata::default(),
+            undo: None,
+            versions: VersionHistory::default(),
+            attachments: Vec::new(),
+        });
+    };
+
+    // File was saved with a newer version of this program
+    let Some((&VERSION, rest)) = rest.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unsupported file format version",
+        ));
+    };
+
+    let mut document: Document = rmp_serde::from_slice(rest)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    for attachment in &mut document.attachments {
+        attachment.update_hash();
+    }
+    Ok(document)
+}
+
+/// Decompress bytes, unless they are larger than limit
+pub fn decompress(compressed: &[u8], limit: u64) -> io::Result<Vec<u8>> {
+    read_limited(DeflateDecoder::new(compressed), limit)
+}
+
+/// Read all bytes from decompressing reader, failing if there are more than limit
+pub fn read_limited(reader: impl Read, limit: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Decompressed data is too large",
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Returns `true` if decrypted bytes have metadata, and are not plain text
+fn is_document(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC) || bytes.starts_with(COMPRESSED_MAGIC) || padding::is_padded(bytes)
+}
+
+/// Writes contents of decrypted bytes, as they are decrypted
+///
+/// Plain text is written straight through, but files with metadata are kept until `finish`, as they must be decoded at once
+///
+/// Plain text is not checked to be valid UTF-8
+pub struct ContentsWriter<W> {
+    inner: W,
+    /// Bytes which are not yet written
+    buffer: Vec<u8>,
+    /// Whether bytes start with magic
+    ///
+    /// `None` if not enough bytes were written to tell
+    is_document: Option<bool>,
+}
+
+impl<W: Write> ContentsWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            is_document: None,
+        }
+    }
+
+    /// Write any remaining contents, after all bytes were decrypted
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.is_document != Some(false) {
+            let document = decode(padding::unpad(std::mem::take(&mut self.buffer))?)?;
+            self.inner.write_all(document.contents.as_bytes())?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for ContentsWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.is_document == Some(false) {
+            return self.inner.write(buf);
+        }
+
+        self.buffer.extend_from_slice(buf);
+        if self.is_document.is_none() && self.buffer.len() >= MAGIC.len() {
+            let is_document = is_document(&self.buffer);
+            self.is_document = Some(is_document);
+            if !is_document {
+                self.inner.write_all(&std::mem::take(&mut self.buffer))?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Appended to name of file, to get name of lock file
+const LOCK_EXTENSION: &str = ".lock";
+
+/// Times to try creating lock file, when an old lock file is removed
+const ACQUIRE_ATTEMPTS: usize = 3;
+
+/// Program which holds a lock, written in lock file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LockOwner {
+    /// Name of computer
+    pub host: String,
+    /// Process id of program
+    pub pid: u32,
+    /// Name of user running program
+    pub user: String,
+}
+
+impl LockOwner {
+    /// Get owner for this program
+    fn current() -> Self {
+        Self {
+            host: hostname(),
+            pid: process::id(),
+            user: whoami::username(),
+        }
+    }
+
+    /// Returns `true` if owner is this program
+    fn is_current(&self) -> bool {
+        *self == Self::current()
+    }
+
+    /// Returns `true` if owner is a program on this computer which is no longer running
+    ///
+    /// Always `false` on platforms where this cannot be checked
+    fn is_stale(&self) -> bool {
+        if self.host != hostname() {
+            return false;
+        }
+
+        #[cfg(target_os = "linux")]
+        return !PathBuf::from("/proc").join(self.pid.to_string()).exists();
+
+        #[cfg(not(target_os = "linux"))]
+        return false;
+    }
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on {} (process {})", self.user, self.host, self.pid)
+    }
+}
+
+/// Advisory lock of a file, so it is not edited by two programs at once
+///
+/// Lock is a file next to the file, with `.lock` appended to its name, which contains the owner
+///
+/// Lock is released when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    /// Path of lock file
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Lock file at path
+    ///
+    /// Returns owner of lock if file is already locked by another program
+    ///
+    /// Locks which are invalid, or held by a program which is no longer running, are removed and created again
+    pub fn acquire(path: &str) -> io::Result<Result<Self, LockOwner>> {
+        let lock_path = lock_path(path);
+        let owner = LockOwner::current();
+        let text = toml::to_string(&owner)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        // Old lock is removed and created again, rather than overwritten,
+        //      so a lock created by another program at the same time is not replaced
+        for _ in 0..ACQUIRE_ATTEMPTS {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut file) => {
+                    file.write_all(text.as_bytes())?;
+                    return Ok(Ok(Self { path: lock_path }));
+                }
+
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                    if let Some(existing) = read_owner(&lock_path) {
+                        if !existing.is_current() && !existing.is_stale() {
+                            return Ok(Err(existing));
+                        }
+                    }
+
+                    println!("Removing old lock file");
+                    match fs::remove_file(&lock_path) {
+                        // Lock may have been removed by another program
+                        Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                        _ => (),
+                    }
+                }
+
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "Lock file was created again while locking",
+        ))
+    }
+
+    /// Returns `true` if lock is of file at path
+    pub fn is_for(&self, path: &str) -> bool {
+        self.path == lock_path(path)
+    }
+}
+
+impl Drop for FileLock {
+    /// Remove lock file, unless it was replaced by another program
+    fn drop(&mut self) {
+        if read_owner(&self.path).is_some_and(|owner| owner.is_current()) {
+            if let Err(error) = fs::remove_file(&self.path) {
+                eprintln!("Failed to remove lock file: {:?}", error);
+            }
+        }
+    }
+}
+
+/// Get name of computer, or empty string if unknown
+fn hostname() -> String {
+    whoami::fallible::hostname().unwrap_or_default()
+}
+
+/// Get path of lock file, for file at path
+fn lock_path(path: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", path, LOCK_EXTENSION))
+}
+
+/// Read owner from lock file
+///
+/// Returns `None` if file does not exist or is invalid
+fn read_owner(path: &PathBuf) -> Option<LockOwner> {
+    let text = fs::read_to_string(path).ok()?;
+    toml::from_str(&text).ok()
+}
@@ -0,0 +1,278 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use ::age::x25519;
+use chrono::Local;
+
+use super::{
+    age::{self, AgeKey},
+    permissions, Container, File, FileLock,
+};
+
+/// Appended to name of file, with `.bak`, to get name of backup
+const BACKUP_SUFFIX: &str = ".legacy";
+
+/// Appended to name of file, while new file is written
+const TEMP_EXTENSION: &str = ".migrating";
+
+/// Start of name of report file, which is written in migrated directory
+const REPORT_PREFIX: &str = "migration-report-";
+
+/// How migrated files are encrypted
+///
+/// Migrated files are age files with only the text, so they can also be decrypted with `age -d`
+pub enum MigrationTarget {
+    /// Passphrase, with scrypt
+    Password(String),
+    /// Public keys of recipients, with X25519
+    Recipients(Vec<x25519::Recipient>),
+}
+
+impl MigrationTarget {
+    /// Read public keys of recipients, such as `age1...`
+    ///
+    /// Public key of identity in config directory is added if `include_identity` is `true`,
+    ///     so migrated files can be opened by this program
+    pub fn recipients(text: &str, include_identity: bool) -> io::Result<Self> {
+        let mut recipients = age::parse_recipients(text)?;
+        if include_identity {
+            recipients.push(age::identity()?.to_public());
+        }
+
+        if recipients.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No recipients"));
+        }
+        Ok(Self::Recipients(recipients))
+    }
+
+    /// Format of migrated files
+    fn container(&self) -> Container {
+        match self {
+            Self::Password(_) => Container::AgePassphrase,
+            Self::Recipients(_) => Container::AgeX25519,
+        }
+    }
+
+    /// Key to encrypt age file with
+    fn key(&self) -> AgeKey<'_> {
+        match self {
+            Self::Password(password) => AgeKey::Passphrase(password),
+            Self::Recipients(recipients) => AgeKey::Recipients(recipients),
+        }
+    }
+}
+
+/// Result of migrating a directory
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Directory which was migrated
+    pub directory: String,
+    /// Paths of files which were migrated
+    pub migrated: Vec<String>,
+    /// Paths of files which could not be migrated, with reason
+    pub failed: Vec<(String, String)>,
+}
+
+impl MigrationReport {
+    /// Get report as text, with every file
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // Writing to string cannot fail
+        let _ = writeln!(
+            text,
+            "Migration of {}, at {}",
+            self.directory,
+            Local::now().format("%Y-%m-%d %H:%M:%S")
+        );
+        let _ = writeln!(
+            text,
+            "{} migrated, {} failed",
+            self.migrated.len(),
+            self.failed.len()
+        );
+
+        text.push_str("\nMigrated:\n");
+        for path in &self.migrated {
+            let _ = writeln!(text, "    {}", path);
+        }
+
+        text.push_str("\nFailed:\n");
+        for (path, reason) in &self.failed {
+            let _ = writeln!(text, "    {}: {}", path, reason);
+        }
+        text
+    }
+
+    /// Write report in migrated directory
+    ///
+    /// Returns path of report file
+    pub fn write(&self) -> io::Result<String> {
+        let name = format!(
+            "{}{}.txt",
+            REPORT_PREFIX,
+            Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let path = Path::new(&self.directory).join(name).display().to_string();
+        permissions::create(&path)?.write_all(self.to_text().as_bytes())?;
+        Ok(path)
+    }
+}
+
+/// Re-encrypt every file with extension in directory and its subdirectories, which uses the legacy key
+///
+/// Original files are copied next to each file with `.legacy.bak` appended, if `backup` is `true`
+/// Existing backups are not overwritten, and a number is added instead, such as `.legacy.2.bak`
+///
+/// Files with attachments, versions, undo history, padding, compression or a language are not migrated,
+///     as age files only save text
+///
+/// Files which fail do not stop migration, and are included in report
+pub fn migrate_directory(
+    directory: &str,
+    extension: &str,
+    legacy_key: &str,
+    target: &MigrationTarget,
+    backup: bool,
+) -> io::Result<MigrationReport> {
+    let mut paths = Vec::new();
+    find_files(Path::new(directory), extension, &mut paths)?;
+    paths.sort();
+
+    let mut report = MigrationReport {
+        directory: directory.to_string(),
+        ..Default::default()
+    };
+
+    for path in paths {
+        let path = path.display().to_string();
+        match migrate_file(&path, legacy_key, target, backup) {
+            Ok(()) => report.migrated.push(path),
+            Err(reason) => report.failed.push((path, reason)),
+        }
+    }
+    Ok(report)
+}
+
+/// Re-encrypt file which uses the legacy key
+///
+/// Returns reason if file could not be migrated
+fn migrate_file(
+    path: &str,
+    legacy_key: &str,
+    target: &MigrationTarget,
+    backup: bool,
+) -> Result<(), String> {
+    // Do not change files which are being edited
+    let _lock = match FileLock::acquire(path) {
+        Ok(Ok(lock)) => lock,
+        Ok(Err(owner)) => return Err(format!("File is being edited by {}", owner)),
+        Err(error) => return Err(format!("Failed to lock file: {}", error)),
+    };
+
+    let mut file = File::open_path_and_decrypt(path, legacy_key).map_err(describe_error)?;
+    if !file.container.uses_password() {
+        return Err(String::from("File does not use the legacy key"));
+    }
+
+    // Files with anything other than text are not migrated, so nothing is lost silently
+    let lost = file.data_not_in_text_only();
+    if !lost.is_empty() {
+        return Err(format!(
+            "File has {}, which age files cannot save",
+            lost.join(", ")
+        ));
+    }
+    file.container = target.container();
+    file.metadata.keep_versions = false;
+
+    // Write new file next to original, so original is not changed if this fails
+    let temp_path = format!("{}{}", path, TEMP_EXTENSION);
+    let result = write_migrated(&file, &temp_path, target).and_then(|()| {
+        if backup {
+            backup_file(path)?;
+        }
+        Ok(fs::rename(&temp_path, path)?)
+    });
+
+    if let Err(error) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(describe_error(error));
+    }
+    Ok(())
+}
+
+/// Copy file to a new backup next to it, without overwriting existing backups
+///
+/// Backup of `a.enc` is `a.enc.legacy.bak`, or `a.enc.legacy.2.bak` if that exists, and so on
+///
+/// Returns path of backup
+fn backup_file(path: &str) -> io::Result<String> {
+    let mut original = fs::File::open(path)?;
+    let mut number = 1;
+    loop {
+        let backup_path = if number == 1 {
+            format!("{}{}.bak", path, BACKUP_SUFFIX)
+        } else {
+            format!("{}{}.{}.bak", path, BACKUP_SUFFIX, number)
+        };
+
+        let mut backup = match permissions::create_new(&backup_path) {
+            Ok(backup) => backup,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                number += 1;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        if let Err(error) = io::copy(&mut original, &mut backup) {
+            let _ = fs::remove_file(&backup_path);
+            return Err(error);
+        }
+        return Ok(backup_path);
+    }
+}
+
+/// Encrypt file with target, as a new file at path
+///
+/// Only contents are kept, as text, without adding a version
+fn write_migrated(file: &File, path: &str, target: &MigrationTarget) -> Result<(), cocoon::Error> {
+    let bytes = file.encode()?;
+    let mut writer = io::BufWriter::new(permissions::create(path)?);
+    age::encrypt(bytes.as_slice(), &mut writer, target.key())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Add files with extension in directory and its subdirectories to list
+///
+/// Symbolic links are not followed
+fn find_files(directory: &Path, extension: &str, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+
+        if file_type.is_dir() {
+            find_files(&path, extension, paths)?;
+        } else if file_type.is_file() && path.extension().is_some_and(|ext| ext == extension) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Human-readable reason that file could not be migrated
+fn describe_error(error: cocoon::Error) -> String {
+    match error {
+        cocoon::Error::Cryptography => String::from("File does not use the legacy key"),
+        cocoon::Error::UnrecognizedFormat => String::from("Unrecognized file format"),
+        cocoon::Error::TooLarge => String::from("File is too large"),
+        cocoon::Error::TooShort => String::from("File is too short"),
+        cocoon::Error::Io(error) => error.to_string(),
+    }
+}
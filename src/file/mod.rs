@@ -1,10 +1,58 @@
 #[cfg(test)]
 mod tests;
 
-use std::{fs, io};
+/// Read and write age files, with passphrase or X25519 identity
+mod age;
+/// Encrypted data as text, for text-only channels such as email
+mod armor;
+/// Choice of algorithm, for formats which support more than one
+mod cipher;
+/// Detect changes to file by other programs
+mod disk;
+/// Serialize contents and metadata, before encryption
+mod document;
+/// Lock files, so they are not edited by two programs at once
+mod lock;
+/// Re-encrypt files which use the legacy key, with a password or recipients
+mod migrate;
+/// Pad data before encryption, to hide its length
+mod padding;
+/// Create files securely, and check permissions of existing files
+mod permissions;
+/// Read and write OpenPGP messages, encrypted with a password
+mod pgp;
+/// Encrypt and decrypt in segments, so large files use bounded memory
+mod stream;
+
+use std::{
+    fs,
+    io::{self, BufRead, Read, Write},
+    ops::Range,
+};
 
 use cocoon::Cocoon;
 
+use self::{
+    age::AgeKey,
+    disk::{DiskState, Hashing},
+    document::ContentsWriter,
+};
+pub use self::{
+    age::{identity as age_identity, identity_path as age_identity_path},
+    cipher::Cipher,
+    document::{decompress, Attachment, Metadata, MAX_DECOMPRESSED_SIZE},
+    lock::{FileLock, LockOwner},
+    migrate::{migrate_directory, MigrationReport, MigrationTarget},
+    padding::Padding,
+    permissions::remove_securely,
+};
+use crate::{highlight::Language, undo::UndoHistory, versions::VersionHistory};
+
+/// Largest file in stream format which is opened in the editor, in bytes
+///
+/// Larger files can be decrypted with the command line, which does not load them into memory
+pub const MAX_EDITOR_STREAM_SIZE: u64 = 64 * 1024 * 1024;
+
 /// Simple file handler API
 #[derive(Clone, Default)]
 pub struct File {
@@ -16,9 +64,193 @@ pub struct File {
     contents: String,
     /// Whether file is saved
     saved: bool,
+    /// Information about file, such as language
+    metadata: Metadata,
+    /// Undo and redo history of contents
+    undo: UndoHistory,
+    /// Contents at previous saves
+    versions: VersionHistory,
+    /// State of file on disk, when last opened or saved
+    ///
+    /// `None` if file was never opened or saved at current path
+    disk_state: Option<DiskState>,
+    /// Why file cannot be edited or saved
+    ///
+    /// `None` if file can be edited
+    read_only: Option<ReadOnly>,
+    /// Format of encrypted file
+    container: Container,
+    /// Algorithm to encrypt file with, if format supports a choice
+    cipher: Cipher,
+    /// Binary files stored with contents
+    attachments: Vec<Attachment>,
+    /// Whether file is saved as armored text, instead of binary
+    armored: bool,
+}
+
+/// Format of encrypted file
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Container {
+    /// Whole file is encrypted at once, with `cocoon`
+    #[default]
+    Cocoon,
+    /// File is encrypted in segments, for large files
+    Stream,
+    /// age file, encrypted with a passphrase
+    AgePassphrase,
+    /// age file, encrypted to X25519 identity in config directory
+    AgeX25519,
+    /// OpenPGP message, encrypted with a password
+    Pgp,
+}
+
+impl Container {
+    /// All formats, in order shown to user
+    pub const ALL: [Self; 5] = [
+        Self::Cocoon,
+        Self::Stream,
+        Self::AgePassphrase,
+        Self::AgeX25519,
+        Self::Pgp,
+    ];
+
+    /// Human-readable name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cocoon => "cocoon",
+            Self::Stream => "stream",
+            Self::AgePassphrase => "age, passphrase",
+            Self::AgeX25519 => "age, X25519",
+            Self::Pgp => "OpenPGP",
+        }
+    }
+
+    /// Returns `true` if cipher can be chosen for this format
+    ///
+    /// Other formats always use the same algorithm
+    pub fn supports_cipher(self) -> bool {
+        matches!(self, Self::Cocoon | Self::Stream)
+    }
+
+    /// Returns `true` if format is encrypted with a password
+    ///
+    /// Files encrypted to the X25519 identity do not use a password
+    pub fn uses_password(self) -> bool {
+        self != Self::AgeX25519
+    }
+
+    /// Returns `true` if user is asked for passphrase, instead of using the legacy key
+    pub fn asks_passphrase(self) -> bool {
+        matches!(self, Self::AgePassphrase | Self::Pgp)
+    }
+
+    /// Returns `true` if only contents are saved, as plain text inside encryption
+    ///
+    /// Other programs, such as `age -d` and `gpg -d`, can read these files,
+    ///     but metadata, undo history, versions and attachments are not saved
+    pub fn is_text_only(self) -> bool {
+        matches!(self, Self::AgePassphrase | Self::AgeX25519 | Self::Pgp)
+    }
+
+    /// Human-readable name of encryption algorithm, with given cipher if supported
+    pub fn algorithm(self, cipher: Cipher) -> &'static str {
+        match self {
+            Self::Cocoon | Self::Stream => cipher.name(),
+            Self::AgePassphrase | Self::AgeX25519 => "ChaCha20-Poly1305",
+            Self::Pgp => "AES-256",
+        }
+    }
+
+    /// Human-readable description of how key is derived, or exchanged
+    pub fn key_derivation(self) -> &'static str {
+        match self {
+            Self::Cocoon | Self::Stream => "PBKDF2-SHA256, 100,000 iterations",
+            Self::AgePassphrase => "scrypt",
+            Self::AgeX25519 => "X25519 identity in config directory",
+            Self::Pgp => "Iterated and salted S2K",
+        }
+    }
+
+    /// Human-readable algorithm and format, with given cipher if supported
+    pub fn describe(self, cipher: Cipher) -> String {
+        format!("{} ({})", self.algorithm(cipher), self.name())
+    }
+
+    /// Detect format from start of encrypted file
+    pub fn detect(header: &[u8]) -> Self {
+        if stream::is_stream(header) {
+            Self::Stream
+        } else if age::is_age(header) && age::is_passphrase(header) {
+            Self::AgePassphrase
+        } else if age::is_age(header) {
+            Self::AgeX25519
+        } else if pgp::is_pgp(header) {
+            Self::Pgp
+        } else {
+            Self::Cocoon
+        }
+    }
+}
+
+/// Reason that file cannot be edited or saved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadOnly {
+    /// Chosen by user, to prevent accidental edits
+    User,
+    /// File is being edited by another program
+    Locked,
+    /// Permission to write file was denied
+    NotWritable,
+}
+
+impl ReadOnly {
+    /// Returns `true` if user cannot make file editable
+    pub fn is_forced(self) -> bool {
+        self != Self::User
+    }
+
+    /// Human-readable reason
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::User => "Read-only mode is on",
+            Self::Locked => "File is being edited by another program",
+            Self::NotWritable => "Permission to write file was denied",
+        }
+    }
 }
 
 impl File {
+    /// Create unregistered file from unencrypted text file at path
+    ///
+    /// File is not saved, and has no path, so it must be saved as a new file
+    pub fn import_plaintext(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+
+        Ok(Self {
+            undo: UndoHistory::new(&contents),
+            contents,
+            ..Default::default()
+        })
+    }
+
+    /// Write contents to path, without encryption
+    ///
+    /// New files can only be read and written by owner, on Unix
+    pub fn export_decrypted(&self, path: &str) -> io::Result<()> {
+        permissions::create(path)?.write_all(self.contents.as_bytes())
+    }
+
+    /// Write attachment at index to path, without encryption
+    ///
+    /// New files can only be read and written by owner, on Unix
+    pub fn export_attachment(&self, index: usize, path: &str) -> io::Result<()> {
+        let attachment = self
+            .attachments
+            .get(index)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Attachment does not exist"))?;
+        permissions::create(path)?.write_all(&attachment.data)
+    }
+
     /// Returns `true` if file does not have an associated filepath (was never saved)
     fn is_registered(&self) -> bool {
         self.path.is_some()
@@ -78,77 +310,740 @@ impl File {
     }
 
     /// Set filepath
+    ///
+    /// Forgets state of file on disk, if path changed
     pub fn set_path(&mut self, path: impl Into<String>) {
-        self.path = Some(path.into())
+        let path = path.into();
+        if self.path.as_ref() != Some(&path) {
+            self.disk_state = None;
+        }
+        self.path = Some(path)
+    }
+
+    /// Returns `true` if file on disk can be read or written by users other than owner
+    ///
+    /// Always `false` if file is not registered, or on platforms other than Unix
+    pub fn has_insecure_permissions(&self) -> bool {
+        let Some(path) = &self.path else {
+            return false;
+        };
+
+        permissions::is_insecure(path).unwrap_or_else(|error| {
+            eprintln!("Failed to read permissions: {:?}", error);
+            false
+        })
+    }
+
+    /// Remove permissions of users other than owner, from file on disk
+    pub fn fix_permissions(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => permissions::make_secure(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `true` if file on disk was changed by another program, since it was opened or saved
+    pub fn is_changed_on_disk(&self) -> bool {
+        match (&self.path, &self.disk_state) {
+            (Some(path), Some(disk_state)) => disk_state.is_changed(path),
+            _ => false,
+        }
+    }
+
+    /// Accept current file on disk, so it is no longer counted as changed
+    ///
+    /// This allows saving over changes made by another program
+    pub fn ignore_changes_on_disk(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        self.disk_state = DiskState::read(path).ok();
+    }
+
+    /// Update from a copy of this file, which was saved on another thread
+    ///
+    /// Keeps contents, as they may have changed while saving
+    ///
+    /// Save state is only set to saved if nothing which is saved has changed since copy was made,
+    ///     so changes made while saving are saved later
+    pub fn finish_save(&mut self, saved: File) {
+        if self.saves_same_as(&saved) {
+            self.force_set_saved();
+        }
+        self.versions = saved.versions;
+        self.disk_state = saved.disk_state;
+    }
+
+    /// Returns `true` if file would be saved the same as other file
+    ///
+    /// Undo history and versions are not compared, as they only change with contents
+    fn saves_same_as(&self, other: &File) -> bool {
+        self.path == other.path
+            && self.contents == other.contents
+            && self.metadata == other.metadata
+            && self.attachments == other.attachments
+            && self.container == other.container
+            && self.cipher == other.cipher
+            && self.armored == other.armored
+    }
+
+    /// Returns `true` if file cannot be edited or saved
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.is_some()
+    }
+
+    /// Get reason that file cannot be edited or saved
+    ///
+    /// `None` if file can be edited
+    pub fn read_only(&self) -> Option<ReadOnly> {
+        self.read_only
+    }
+
+    /// Set reason that file cannot be edited or saved
+    ///
+    /// `None` to make file editable
+    pub fn set_read_only(&mut self, read_only: Option<ReadOnly>) {
+        self.read_only = read_only;
+    }
+
+    /// Get language of contents
+    pub fn language(&self) -> Language {
+        self.metadata.language
+    }
+
+    /// Set language of contents
+    ///
+    /// Sets save state to unsaved, as language is saved in file
+    pub fn set_language(&mut self, language: Language) {
+        if language != self.metadata.language {
+            self.metadata.language = language;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Record change to contents in undo history
+    ///
+    /// Should be run after every change to contents, other than undo and redo
+    pub fn record_edit(&mut self) {
+        self.undo.record(&self.contents);
+    }
+
+    /// Get undo history of contents
+    pub fn undo_history(&self) -> &UndoHistory {
+        &self.undo
+    }
+
+    /// Undo last edit to contents
+    ///
+    /// Returns byte range of restored text
+    pub fn undo(&mut self) -> Option<Range<usize>> {
+        let range = self.undo.undo(&mut self.contents)?;
+        self.mark_as_unsaved();
+        Some(range)
+    }
+
+    /// Redo last undone edit to contents
+    ///
+    /// Returns byte range of restored text
+    pub fn redo(&mut self) -> Option<Range<usize>> {
+        let range = self.undo.redo(&mut self.contents)?;
+        self.mark_as_unsaved();
+        Some(range)
+    }
+
+    /// Undo or redo edits, until the given number of edits are applied
+    ///
+    /// Returns byte range of text changed by the last edit
+    pub fn undo_to(&mut self, applied: usize) -> Option<Range<usize>> {
+        let range = self.undo.jump_to(applied, &mut self.contents)?;
+        self.mark_as_unsaved();
+        Some(range)
+    }
+
+    /// Returns `true` if undo history is saved in file
+    pub fn persist_undo(&self) -> bool {
+        self.metadata.persist_undo
+    }
+
+    /// Set whether undo history is saved in file
+    ///
+    /// Sets save state to unsaved, so history can be saved or removed
+    pub fn set_persist_undo(&mut self, persist_undo: bool) {
+        if persist_undo != self.metadata.persist_undo {
+            self.metadata.persist_undo = persist_undo;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Get contents at previous saves
+    pub fn versions(&self) -> &VersionHistory {
+        &self.versions
+    }
+
+    /// Get contents at previous saves, as mutable reference
+    ///
+    /// Does not change save state
+    pub fn versions_mut(&mut self) -> &mut VersionHistory {
+        &mut self.versions
+    }
+
+    /// Returns `true` if a version is added on every save
+    pub fn keep_versions(&self) -> bool {
+        self.metadata.keep_versions
+    }
+
+    /// Set whether a version is added on every save
+    ///
+    /// Sets save state to unsaved, as option is saved in file
+    pub fn set_keep_versions(&mut self, keep_versions: bool) {
+        if keep_versions != self.metadata.keep_versions {
+            self.metadata.keep_versions = keep_versions;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Returns `true` if file is compressed before encryption
+    pub fn compress(&self) -> bool {
+        self.metadata.compress
+    }
+
+    /// Set whether file is compressed before encryption
+    ///
+    /// Sets save state to unsaved, so file can be saved with new option
+    pub fn set_compress(&mut self, compress: bool) {
+        if compress != self.metadata.compress {
+            self.metadata.compress = compress;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Get how file is padded before encryption
+    pub fn padding(&self) -> Padding {
+        self.metadata.padding
+    }
+
+    /// Set how file is padded before encryption
+    ///
+    /// Sets save state to unsaved, so file can be saved with new option
+    pub fn set_padding(&mut self, padding: Padding) {
+        if padding != self.metadata.padding {
+            self.metadata.padding = padding;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Returns `true` if file has data which cannot be saved in its format
+    ///
+    /// Formats which only save text cannot save undo history, versions or attachments,
+    ///     see `Container::is_text_only`
+    pub fn has_data_not_in_format(&self) -> bool {
+        self.container.is_text_only()
+            && (self.metadata.persist_undo
+                || self.metadata.keep_versions
+                || !self.versions.versions().is_empty()
+                || !self.attachments.is_empty())
+    }
+
+    /// Get names of data which would be lost, if file was saved in a format which only saves text
+    ///
+    /// Setting to keep versions is not included, as it does not lose anything already saved
+    ///
+    /// Empty if file only has text
+    pub fn data_not_in_text_only(&self) -> Vec<&'static str> {
+        let mut data = Vec::new();
+        if !self.attachments.is_empty() {
+            data.push("attachments");
+        }
+        if !self.versions.versions().is_empty() {
+            data.push("versions");
+        }
+        if self.metadata.persist_undo {
+            data.push("undo history");
+        }
+        if self.metadata.padding != Padding::None {
+            data.push("padding");
+        }
+        if self.metadata.compress {
+            data.push("compression");
+        }
+        if self.metadata.language != Language::default() {
+            data.push("language");
+        }
+        data
+    }
+
+    /// Get binary files stored with contents
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// Add binary file, to be stored with contents
+    ///
+    /// Name is changed if another attachment has the same name
+    ///
+    /// Sets save state to unsaved
+    pub fn add_attachment(&mut self, name: &str, data: Vec<u8>) {
+        let mut unique_name = name.to_string();
+        let mut number = 1;
+        while self
+            .attachments
+            .iter()
+            .any(|attachment| attachment.name == unique_name)
+        {
+            number += 1;
+            unique_name = format!("{} ({})", name, number);
+        }
+
+        self.attachments.push(Attachment::new(unique_name, data));
+        self.mark_as_unsaved();
+    }
+
+    /// Remove attachment at index
+    ///
+    /// Sets save state to unsaved
+    pub fn remove_attachment(&mut self, index: usize) {
+        if index < self.attachments.len() {
+            self.attachments.remove(index);
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Get description of encryption used to save file
+    pub fn encryption_mode(&self) -> String {
+        self.container.describe(self.cipher)
+    }
+
+    /// Get format of encrypted file
+    pub fn container(&self) -> Container {
+        self.container
+    }
+
+    /// Set format of encrypted file
+    ///
+    /// Sets save state to unsaved, so file can be saved in new format
+    pub fn set_container(&mut self, container: Container) {
+        if container != self.container {
+            self.container = container;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Get algorithm to encrypt file with
+    ///
+    /// Only used if format supports a choice, see `Container::supports_cipher`
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    /// Set algorithm to encrypt file with
+    ///
+    /// Sets save state to unsaved, so file can be saved with new cipher
+    pub fn set_cipher(&mut self, cipher: Cipher) {
+        if cipher != self.cipher {
+            self.cipher = cipher;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Returns `true` if file is saved as armored text, instead of binary
+    pub fn is_armored(&self) -> bool {
+        self.armored
+    }
+
+    /// Set whether file is saved as armored text, instead of binary
+    ///
+    /// Sets save state to unsaved, so file can be saved in new format
+    pub fn set_armored(&mut self, armored: bool) {
+        if armored != self.armored {
+            self.armored = armored;
+            self.mark_as_unsaved();
+        }
+    }
+
+    /// Encrypt file as armored text, to copy into text-only channels
+    ///
+    /// OpenPGP messages use their own armor, so they can be read by other programs
+    ///
+    /// Does not change file, or add a version
+    pub fn to_armored(&self, key: &str) -> Result<String, cocoon::Error> {
+        let mut encrypted = Vec::new();
+        if self.container == Container::Pgp {
+            pgp::encrypt(&self.encode()?, &mut encrypted, key, true)?;
+            return String::from_utf8(encrypted)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error).into());
+        }
+
+        self.encrypt(&mut encrypted, key)?;
+        Ok(armor::armor(&encrypted))
+    }
+
+    /// Decrypt armored text, such as from clipboard
+    ///
+    /// Returns unregistered `File`, which must be saved as a new file
+    pub fn from_armored(text: &str, key: &str) -> Result<Self, cocoon::Error> {
+        if pgp::is_armored(text.as_bytes()) {
+            let bytes = pgp::decrypt(text.as_bytes(), key)?;
+            let mut file = Self::from_decrypted(bytes, Container::Pgp)?;
+            file.armored = true;
+            return Ok(file);
+        }
+
+        let encrypted = armor::dearmor(text)?;
+        let container = Container::detect(&encrypted);
+        let cipher = detect_cipher(&encrypted, container);
+        let bytes = decrypt(encrypted.as_slice(), container, key)?;
+        let mut file = Self::from_decrypted(bytes, container)?;
+        file.cipher = cipher;
+        Ok(file)
     }
 
     /// Save encrypted file to given path
     ///
+    /// Adds contents to version history, if enabled
+    ///
+    /// File on disk is only replaced if encryption succeeds
+    ///
     /// Sets save state to saved
     pub fn save_to_path_encrypted(&mut self, path: &str, key: &str) -> Result<(), cocoon::Error> {
-        // Create encryptor
-        let cocoon = Cocoon::new(key.as_bytes());
+        if self.metadata.keep_versions {
+            self.versions.add(&self.contents)?;
+        }
 
-        // Get content as bytes
-        let bytes = self.contents.clone().into_bytes().to_vec();
+        // Encrypt data, and write to file through a temporary file,
+        //      so file is not changed if encryption fails
+        // Written bytes are hashed, to detect changes by other programs
+        let mut hash = 0;
+        replace_file(path, |writer| {
+            let mut writer = Hashing::new(writer);
+            if self.armored {
+                writer.write_all(self.to_armored(key)?.as_bytes())?;
+            } else {
+                self.encrypt(&mut writer, key)?;
+            }
+            writer.flush()?;
+            hash = writer.finish();
+            Ok(())
+        })?;
 
-        // Open file (creates new if not already existing)
-        let mut file = match fs::File::create(path) {
-            Ok(file) => file,
+        self.disk_state = Some(DiskState::new(hash, &fs::metadata(path)?));
 
-            // Return an IO error if failed
-            Err(error) => return Err(cocoon::Error::Io(error)),
-        };
+        self.saved = true;
+        Ok(())
+    }
 
-        // Write encrypted data to file
-        cocoon.dump(bytes, &mut file)?;
+    /// Encrypt contents and metadata, in format of file
+    fn encrypt(&self, writer: &mut impl Write, key: &str) -> Result<(), cocoon::Error> {
+        let bytes = self.encode()?;
 
-        self.saved = true;
+        match self.container {
+            Container::Cocoon => Cocoon::new(key.as_bytes())
+                .with_cipher(self.cipher.to_cocoon())
+                .dump(bytes, writer)?,
+            Container::Stream => {
+                stream::encrypt_stream(bytes.as_slice(), writer, key, self.cipher)?
+            }
+            Container::AgePassphrase => {
+                age::encrypt(bytes.as_slice(), writer, AgeKey::Passphrase(key))?
+            }
+            Container::AgeX25519 => age::encrypt(
+                bytes.as_slice(),
+                writer,
+                AgeKey::Identity(&age::identity()?),
+            )?,
+            Container::Pgp => pgp::encrypt(&bytes, writer, key, false)?,
+        }
         Ok(())
     }
 
+    /// Get contents and metadata as bytes, to be encrypted
+    ///
+    /// Formats which only save text get contents as UTF-8, without padding or compression,
+    ///     and fail if other data would be lost
+    fn encode(&self) -> io::Result<Vec<u8>> {
+        if self.container.is_text_only() {
+            if self.has_data_not_in_format() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Format only saves text, without undo history, versions or attachments",
+                ));
+            }
+            return Ok(self.contents.as_bytes().to_vec());
+        }
+
+        // Get content and metadata as bytes
+        // Undo history is only included if enabled for this file
+        let undo = self.metadata.persist_undo.then_some(&self.undo);
+        let bytes = document::encode(
+            &self.contents,
+            &self.metadata,
+            undo,
+            &self.versions,
+            &self.attachments,
+        )?;
+        Ok(padding::pad(bytes, self.metadata.padding))
+    }
+
     /// Open encrypted file from given path
     ///
     /// Returns saved `File` with contents and associated path
+    ///
+    /// File is read-only if it cannot be written
+    ///
+    /// Files in stream format larger than `MAX_EDITOR_STREAM_SIZE` are refused
     pub fn open_path_and_decrypt(
         path: impl Into<String>,
         key: &str,
     ) -> Result<Self, cocoon::Error> {
         let path = path.into();
 
-        // Create decryptor
-        let cocoon = Cocoon::new(key.as_bytes());
+        // Read existing file, hashing bytes to detect changes by other programs
+        let input = fs::File::open(&path)?;
+        let size = input.metadata()?.len();
+        let mut reader = Hashing::new(io::BufReader::new(input));
+        let header = reader.inner_mut().fill_buf()?;
+        let armored = armor::is_armored(header);
+        let container = Container::detect(header);
 
-        // Open existing file
-        let mut file = match fs::File::open(&path) {
-            Ok(file) => file,
+        // Editor keeps several copies of contents in memory, but command line does not
+        if container == Container::Stream && size > MAX_EDITOR_STREAM_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                "File is too large for the editor, decrypt it with the command line instead",
+            )
+            .into());
+        }
+        let cipher = detect_cipher(header, container);
+        // OpenPGP messages have their own armor
+        let pgp_armored = pgp::is_armored(header);
 
-            // Return an IO error if failed
-            Err(error) => return Err(cocoon::Error::Io(error)),
+        // Decrypt data (bytes) from file
+        let mut file = if armored {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            Self::from_armored(&text, key)?
+        } else {
+            let bytes = decrypt(&mut reader, container, key)?;
+            let mut file = Self::from_decrypted(bytes, container)?;
+            file.cipher = cipher;
+            file
         };
 
-        // Decrypt data (bytes) from file
-        let bytes = cocoon.parse(&mut file)?;
-
-        // Convert bytes to string
-        // This may fail, if bytes do not form a valid utf8 string
-        let contents = match String::from_utf8(bytes) {
-            Ok(string) => string,
-
-            // Bytes-to-string conversion failed
-            // Return IO error of 'Invalid Data'
-            Err(error) => {
-                return Err(cocoon::Error::from(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    error,
-                )))
-            }
+        // Hash any remaining bytes, so whole file is hashed
+        io::copy(&mut reader, &mut io::sink())?;
+        let disk_state = DiskState::new(reader.finish(), &fs::metadata(&path)?);
+
+        file.read_only = (!is_writable(&path)).then_some(ReadOnly::NotWritable);
+        file.path = Some(path);
+        file.saved = true;
+        file.disk_state = Some(disk_state);
+        file.armored = armored || pgp_armored;
+        Ok(file)
+    }
+
+    /// Detect format of encrypted file at path, without decrypting it
+    pub fn detect_container(path: &str) -> io::Result<Container> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+        let header = reader.fill_buf()?;
+        if !armor::is_armored(header) {
+            return Ok(Container::detect(header));
+        }
+
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Self::detect_armored_container(&text)
+    }
+
+    /// Detect format of armored text, without decrypting it
+    pub fn detect_armored_container(text: &str) -> io::Result<Container> {
+        if pgp::is_armored(text.as_bytes()) {
+            return Ok(Container::Pgp);
+        }
+        Ok(Container::detect(&armor::dearmor(text)?))
+    }
+
+    /// Create unregistered file from decrypted bytes
+    fn from_decrypted(bytes: Vec<u8>, container: Container) -> Result<Self, cocoon::Error> {
+        // Convert bytes to contents and metadata
+        // This may fail, if bytes are not valid
+        let document = document::decode(padding::unpad(bytes)?)?;
+
+        // Continue saved undo history, or start a new one
+        let undo = match document.undo {
+            Some(undo) => undo.resume(&document.contents),
+            None => UndoHistory::new(&document.contents),
         };
 
         Ok(Self {
-            contents,
-            path: Some(path),
-            saved: true,
+            contents: document.contents,
+            metadata: document.metadata,
+            undo,
+            versions: document.versions,
+            container,
+            attachments: document.attachments,
+            ..Default::default()
         })
     }
 }
+
+/// Read cipher from start of encrypted file, in given format
+///
+/// Default cipher if format does not support a choice
+fn detect_cipher(header: &[u8], container: Container) -> Cipher {
+    match container {
+        Container::Cocoon => Cipher::from_cocoon_header(header),
+        Container::Stream => stream::cipher(header),
+        _ => None,
+    }
+    .unwrap_or_default()
+}
+
+/// Decrypt data (bytes), in given format
+///
+/// Cipher is read from file, so it does not need to be given
+fn decrypt(
+    mut reader: impl Read,
+    container: Container,
+    key: &str,
+) -> Result<Vec<u8>, cocoon::Error> {
+    match container {
+        Container::Cocoon => Cocoon::new(key.as_bytes()).parse(&mut reader),
+        Container::Stream => {
+            let mut bytes = Vec::new();
+            stream::decrypt_stream(reader, &mut bytes, key)?;
+            Ok(bytes)
+        }
+        Container::AgePassphrase => {
+            let mut bytes = Vec::new();
+            age::decrypt(reader, &mut bytes, AgeKey::Passphrase(key))?;
+            Ok(bytes)
+        }
+        Container::AgeX25519 => {
+            let mut bytes = Vec::new();
+            age::decrypt(reader, &mut bytes, AgeKey::Identity(&age::identity()?))?;
+            Ok(bytes)
+        }
+        Container::Pgp => pgp::decrypt(reader, key),
+    }
+}
+
+/// Appended to name of output file, while it is written
+const TEMP_EXTENSION: &str = ".tmp";
+
+/// Encrypt any file, without loading it into memory
+///
+/// Output is in stream format, and can be opened as plain text if input is valid UTF-8
+///
+/// Output is only replaced if encryption succeeds, and cannot be the same file as input
+pub fn encrypt_file(
+    input: &str,
+    output: &str,
+    key: &str,
+    cipher: Cipher,
+) -> Result<(), cocoon::Error> {
+    let reader = io::BufReader::new(fs::File::open(input)?);
+    write_output(input, output, |writer| {
+        stream::encrypt_stream(reader, writer, key, cipher)
+    })
+}
+
+/// Decrypt file, and write its contents
+///
+/// Files in stream format are decrypted without loading them into memory, unless they have metadata
+///
+/// Output is only replaced if decryption succeeds, so unauthenticated contents are never left in it,
+///     and cannot be the same file as input
+pub fn decrypt_file(input: &str, output: &str, key: &str) -> Result<(), cocoon::Error> {
+    let mut reader = io::BufReader::new(fs::File::open(input)?);
+
+    match Container::detect(reader.fill_buf()?) {
+        Container::Cocoon | Container::AgePassphrase | Container::AgeX25519 | Container::Pgp => {
+            let file = File::open_path_and_decrypt(input, key)?;
+            write_output(input, output, |mut writer| {
+                writer.write_all(file.contents.as_bytes())?;
+                Ok(writer.flush()?)
+            })
+        }
+        Container::Stream => write_output(input, output, |writer| {
+            let mut writer = ContentsWriter::new(writer);
+            stream::decrypt_stream(reader, &mut writer, key)?;
+            Ok(writer.finish()?)
+        }),
+    }
+}
+
+/// Write output file through a temporary file next to it, see `replace_file`
+///
+/// Fails if output, or temporary file, is the same file as input, as input would be truncated
+fn write_output(
+    input: &str,
+    output: &str,
+    write: impl FnOnce(io::BufWriter<fs::File>) -> Result<(), cocoon::Error>,
+) -> Result<(), cocoon::Error> {
+    let temp_path = format!("{}{}", output, TEMP_EXTENSION);
+    if is_same_file(input, output) || is_same_file(input, &temp_path) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Input and output must be different files",
+        )
+        .into());
+    }
+
+    replace_file(output, write)
+}
+
+/// Write file through a temporary file next to it, which replaces file if writing succeeds
+///
+/// Temporary file is removed if writing fails, so file is never left incomplete
+///
+/// Permissions of an existing file are kept
+fn replace_file(
+    path: &str,
+    write: impl FnOnce(io::BufWriter<fs::File>) -> Result<(), cocoon::Error>,
+) -> Result<(), cocoon::Error> {
+    let temp_path = format!("{}{}", path, TEMP_EXTENSION);
+
+    let result = permissions::create(&temp_path)
+        .map_err(cocoon::Error::from)
+        .and_then(|file| write(io::BufWriter::new(file)))
+        .and_then(|()| {
+            if let Ok(metadata) = fs::metadata(path) {
+                fs::set_permissions(&temp_path, metadata.permissions())?;
+            }
+            Ok(fs::rename(&temp_path, path)?)
+        });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Returns `true` if both paths are the same existing file, including through links
+fn is_same_file(a: &str, b: &str) -> bool {
+    let (Ok(a_metadata), Ok(b_metadata)) = (fs::metadata(a), fs::metadata(b)) else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        a_metadata.dev() == b_metadata.dev() && a_metadata.ino() == b_metadata.ino()
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (a_metadata, b_metadata);
+        fs::canonicalize(a).ok() == fs::canonicalize(b).ok()
+    }
+}
+
+/// Returns `false` if permission to write file at path is denied
+///
+/// Does not change file
+fn is_writable(path: &str) -> bool {
+    match fs::OpenOptions::new().write(true).open(path) {
+        Ok(_) => true,
+        Err(error) => error.kind() != io::ErrorKind::PermissionDenied,
+    }
+}
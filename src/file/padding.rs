@@ -0,0 +1,100 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+/// Start of decrypted data, for padded files
+///
+/// Followed by length of data as a little-endian `u64`, then data, then zeros
+const MAGIC: &[u8] = b"\xFFETEP";
+
+/// Size of magic and length
+const HEADER_SIZE: usize = MAGIC.len() + 8;
+
+/// Smallest size of padded data, in bytes
+const MIN_SIZE: usize = 256;
+
+/// How data is padded before encryption, to hide its length
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Padding {
+    /// Data is not padded
+    #[default]
+    None,
+    /// Size is rounded up to a power of two
+    PowerOfTwo,
+    /// Size is rounded up to a multiple of given number of bytes
+    Bucket(u32),
+}
+
+impl Padding {
+    /// Default size of buckets, in bytes
+    pub const DEFAULT_BUCKET: u32 = 4096;
+
+    /// Human-readable name
+    pub fn name(self) -> String {
+        match self {
+            Self::None => String::from("No padding"),
+            Self::PowerOfTwo => String::from("Pad to power of two"),
+            Self::Bucket(size) => format!("Pad to {} KiB", size as f32 / 1024.0),
+        }
+    }
+
+    /// Get size of padded data, including header
+    fn padded_size(self, size: usize) -> usize {
+        let size = size.max(MIN_SIZE);
+        match self {
+            Self::None => size,
+            Self::PowerOfTwo => size.next_power_of_two(),
+            Self::Bucket(bucket) => {
+                let bucket = (bucket as usize).max(1);
+                size.div_ceil(bucket) * bucket
+            }
+        }
+    }
+}
+
+/// Pad bytes before encryption, so encrypted size only shows an approximate length
+///
+/// Bytes are not changed if padding is `None`
+pub fn pad(bytes: Vec<u8>, padding: Padding) -> Vec<u8> {
+    if padding == Padding::None {
+        return bytes;
+    }
+
+    let size = padding.padded_size(HEADER_SIZE + bytes.len());
+    let mut padded = Vec::with_capacity(size);
+    padded.extend_from_slice(MAGIC);
+    padded.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    padded.extend_from_slice(&bytes);
+    padded.resize(size, 0);
+    padded
+}
+
+/// Remove padding after decryption
+///
+/// Bytes without magic are not padded, and are returned unchanged
+pub fn unpad(mut bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    if !is_padded(&bytes) {
+        return Ok(bytes);
+    }
+
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid padding");
+    let length = bytes
+        .get(MAGIC.len()..HEADER_SIZE)
+        .and_then(|length| length.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(invalid)?;
+    let end = usize::try_from(length)
+        .ok()
+        .and_then(|length| length.checked_add(HEADER_SIZE))
+        .filter(|&end| end <= bytes.len())
+        .ok_or_else(invalid)?;
+
+    bytes.truncate(end);
+    bytes.drain(..HEADER_SIZE);
+    Ok(bytes)
+}
+
+/// Returns `true` if decrypted bytes are padded
+pub fn is_padded(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
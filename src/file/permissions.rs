@@ -0,0 +1,81 @@
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+/// Permission bits for group and other users
+#[cfg(unix)]
+const GROUP_AND_OTHER: u32 = 0o077;
+
+/// Open file for writing, creating it if it does not exist
+///
+/// New files can only be read and written by owner, on Unix
+pub fn create(path: &str) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    options.open(path)
+}
+
+/// Create new file, which can only be read and written by owner, on Unix
+///
+/// Fails if file already exists
+pub fn create_new(path: &str) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    options.open(path)
+}
+
+/// Returns `true` if file can be read or written by users other than owner
+///
+/// Always `false` on platforms other than Unix
+pub fn is_insecure(path: &str) -> io::Result<bool> {
+    #[cfg(unix)]
+    return Ok(fs::metadata(path)?.permissions().mode() & GROUP_AND_OTHER != 0);
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+/// Remove permissions of users other than owner
+///
+/// Does nothing on platforms other than Unix
+pub fn make_secure(path: &str) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_mode(permissions.mode() & !GROUP_AND_OTHER);
+        fs::set_permissions(path, permissions)?;
+    }
+
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+/// Overwrite file with zeros, then remove it
+///
+/// Copies may still remain on disk, such as on SSDs or copy-on-write file systems
+pub fn remove_securely(path: &str) -> io::Result<()> {
+    let length = fs::metadata(path)?.len();
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    io::copy(&mut io::repeat(0).take(length), &mut file)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::remove_file(path)
+}
@@ -0,0 +1,130 @@
+use std::io::{self, Cursor, Read, Write};
+
+use ::pgp::{
+    crypto::sym::SymmetricKeyAlgorithm,
+    ser::Serialize,
+    types::{CompressionAlgorithm, StringToKey},
+    Deserializable, Message,
+};
+
+use super::document::{read_limited, MAX_DECOMPRESSED_SIZE};
+
+/// First line of armored OpenPGP message
+const ARMOR_BEGIN: &[u8] = b"-----BEGIN PGP MESSAGE-----";
+
+/// Tag of symmetric-key encrypted session key packet, which starts messages encrypted with a password
+const SKESK_TAG: u8 = 3;
+
+/// Returns `true` if bytes are the start of an OpenPGP message, encrypted with a password
+///
+/// Messages may be binary, such as from `gpg --symmetric`, or armored, such as with `--armor`
+pub fn is_pgp(bytes: &[u8]) -> bool {
+    is_armored(bytes) || first_packet_tag(bytes) == Some(SKESK_TAG)
+}
+
+/// Returns `true` if bytes are the start of an armored OpenPGP message
+pub fn is_armored(bytes: &[u8]) -> bool {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    bytes[start..].starts_with(ARMOR_BEGIN)
+}
+
+/// Get tag of first packet, from its header
+///
+/// Returns `None` if first byte is not a packet header
+fn first_packet_tag(bytes: &[u8]) -> Option<u8> {
+    let &header = bytes.first()?;
+    if header & 0x80 == 0 {
+        return None;
+    }
+
+    if header & 0x40 == 0 {
+        // Old format
+        Some((header >> 2) & 0x0F)
+    } else {
+        // New format
+        Some(header & 0x3F)
+    }
+}
+
+/// Encrypt bytes with password, as OpenPGP message
+///
+/// Data is compressed, and encrypted with AES-256, like `gpg --symmetric`
+pub fn encrypt(
+    bytes: &[u8],
+    mut writer: impl Write,
+    password: &str,
+    armored: bool,
+) -> Result<(), cocoon::Error> {
+    let mut rng = rand::thread_rng();
+    let password = password.to_string();
+    let s2k = StringToKey::new_default(&mut rng);
+
+    let message = Message::new_literal_bytes("", bytes)
+        .compress(CompressionAlgorithm::ZLIB)
+        .and_then(|message| {
+            message.encrypt_with_password(&mut rng, s2k, SymmetricKeyAlgorithm::AES256, || password)
+        })
+        .map_err(|_| cocoon::Error::Cryptography)?;
+
+    if armored {
+        message.to_armored_writer(&mut writer, None)
+    } else {
+        message.to_writer(&mut writer)
+    }
+    .map_err(|error| io::Error::other(error.to_string()))?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Decrypt OpenPGP message with password, binary or armored
+pub fn decrypt(mut reader: impl Read, password: &str) -> Result<Vec<u8>, cocoon::Error> {
+    let mut encrypted = Vec::new();
+    reader.read_to_end(&mut encrypted)?;
+
+    let message = if is_armored(&encrypted) {
+        Message::from_armor_single(Cursor::new(encrypted)).map(|(message, _)| message)
+    } else {
+        Message::from_bytes(encrypted.as_slice())
+    }
+    .map_err(|_| cocoon::Error::UnrecognizedFormat)?;
+
+    let password = password.to_string();
+    let mut decrypter = message
+        .decrypt_with_password(|| password)
+        .map_err(|_| cocoon::Error::Cryptography)?;
+
+    let message = decrypter
+        .next()
+        .ok_or(cocoon::Error::Cryptography)?
+        .map_err(|_| cocoon::Error::Cryptography)?;
+    literal_data(message, false)
+}
+
+/// Get data of literal packet, from decrypted message
+///
+/// Compressed data is decompressed up to `MAX_DECOMPRESSED_SIZE`, and only once,
+///     as it can be much larger than the message
+fn literal_data(message: Message, decompressed: bool) -> Result<Vec<u8>, cocoon::Error> {
+    match message {
+        Message::Literal(data) => Ok(data.data().to_vec()),
+        // Signature is not checked, as message is only encrypted with a password
+        Message::Signed {
+            message: Some(message),
+            ..
+        } => literal_data(*message, decompressed),
+        Message::Compressed(data) if !decompressed => {
+            let reader = data
+                .decompress()
+                .map_err(|_| cocoon::Error::UnrecognizedFormat)?;
+            let bytes = read_limited(reader, MAX_DECOMPRESSED_SIZE)?;
+            let message = Message::from_bytes(bytes.as_slice())
+                .map_err(|_| cocoon::Error::UnrecognizedFormat)?;
+            literal_data(message, true)
+        }
+        _ => Err(cocoon::Error::UnrecognizedFormat),
+    }
+}
@@ -0,0 +1,257 @@
+use std::io::{self, Read, Write};
+
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::{
+    aead::{
+        consts::U12,
+        stream::{DecryptorBE32, EncryptorBE32},
+        AeadCore, AeadInPlace, KeyInit, Payload,
+    },
+    ChaCha20Poly1305, Key,
+};
+use hmac::Hmac;
+use rand::RngCore;
+use sha2::Sha256;
+
+use super::Cipher;
+
+/// Start of file, to detect format
+///
+/// Followed by a byte for the cipher
+pub const MAGIC: &[u8] = b"ETESTRM";
+
+/// Bytes of plaintext in each segment
+///
+/// Only two segments are in memory at once
+const SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Bytes of authentication tag added to each segment
+const TAG_SIZE: usize = 16;
+
+/// Bytes of random salt for key derivation
+const SALT_SIZE: usize = 16;
+
+/// Bytes of random nonce prefix, which is followed by a segment counter
+const NONCE_PREFIX_SIZE: usize = 7;
+
+/// Position of cipher byte, after magic
+const CIPHER_INDEX: usize = MAGIC.len();
+
+/// Bytes of header, which is authenticated with every segment
+const HEADER_SIZE: usize = MAGIC.len() + 1 + SALT_SIZE + NONCE_PREFIX_SIZE;
+
+/// Iterations of PBKDF2, same as `Cocoon::new`
+const KDF_ITERATIONS: u32 = 100_000;
+
+/// Returns `true` if bytes are the start of a stream
+pub fn is_stream(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC) && cipher(bytes).is_some()
+}
+
+/// Read cipher from start of stream
+///
+/// `None` if bytes are too short, or cipher is unknown
+pub fn cipher(bytes: &[u8]) -> Option<Cipher> {
+    match bytes.get(CIPHER_INDEX)? {
+        1 => Some(Cipher::ChaCha20Poly1305),
+        2 => Some(Cipher::Aes256Gcm),
+        _ => None,
+    }
+}
+
+/// Byte written after magic, for cipher
+fn cipher_byte(cipher: Cipher) -> u8 {
+    match cipher {
+        Cipher::ChaCha20Poly1305 => 1,
+        Cipher::Aes256Gcm => 2,
+    }
+}
+
+/// Encrypt from reader to writer, in segments
+///
+/// Memory use does not depend on length of data
+///
+/// Format is a header (magic, cipher, salt, and nonce prefix), then each segment of ciphertext,
+///     using the STREAM construction, so segments cannot be reordered or truncated
+pub fn encrypt_stream(
+    reader: impl Read,
+    mut writer: impl Write,
+    key: &str,
+    cipher: Cipher,
+) -> Result<(), cocoon::Error> {
+    let mut header = [0; HEADER_SIZE];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    header[CIPHER_INDEX] = cipher_byte(cipher);
+    rand::thread_rng().fill_bytes(&mut header[CIPHER_INDEX + 1..]);
+    writer.write_all(&header)?;
+
+    match cipher {
+        Cipher::ChaCha20Poly1305 => {
+            encrypt_segments::<ChaCha20Poly1305>(reader, writer, key, &header)
+        }
+        Cipher::Aes256Gcm => encrypt_segments::<Aes256Gcm>(reader, writer, key, &header),
+    }
+}
+
+/// Encrypt each segment after header, with given algorithm
+fn encrypt_segments<A>(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    key: &str,
+    header: &[u8],
+) -> Result<(), cocoon::Error>
+where
+    A: AeadInPlace + KeyInit + AeadCore<NonceSize = U12>,
+{
+    let (salt, nonce_prefix) = header[CIPHER_INDEX + 1..].split_at(SALT_SIZE);
+    let aead =
+        A::new_from_slice(&derive_key(key, salt)).map_err(|_| cocoon::Error::Cryptography)?;
+    let mut encryptor = EncryptorBE32::from_aead(aead, nonce_prefix.into());
+
+    let mut segment = vec![0; SEGMENT_SIZE];
+    let mut next = vec![0; SEGMENT_SIZE];
+    let mut length = read_full(&mut reader, &mut segment)?;
+
+    loop {
+        // Segment is last if it is not full, or there is nothing after it
+        let next_length = if length < SEGMENT_SIZE {
+            0
+        } else {
+            read_full(&mut reader, &mut next)?
+        };
+
+        let payload = Payload {
+            msg: &segment[..length],
+            aad: header,
+        };
+
+        if next_length == 0 {
+            let ciphertext = encryptor
+                .encrypt_last(payload)
+                .map_err(|_| cocoon::Error::Cryptography)?;
+            writer.write_all(&ciphertext)?;
+            break;
+        }
+
+        let ciphertext = encryptor
+            .encrypt_next(payload)
+            .map_err(|_| cocoon::Error::Cryptography)?;
+        writer.write_all(&ciphertext)?;
+
+        std::mem::swap(&mut segment, &mut next);
+        length = next_length;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Decrypt from reader to writer, in segments
+///
+/// Memory use does not depend on length of data
+///
+/// Data written before an error should be discarded, as later segments were not authenticated
+///
+/// Cipher is read from header
+pub fn decrypt_stream(
+    mut reader: impl Read,
+    writer: impl Write,
+    key: &str,
+) -> Result<(), cocoon::Error> {
+    let mut header = [0; HEADER_SIZE];
+    if read_full(&mut reader, &mut header)? < HEADER_SIZE {
+        return Err(cocoon::Error::TooShort);
+    }
+    if !header.starts_with(MAGIC) {
+        return Err(cocoon::Error::UnrecognizedFormat);
+    }
+
+    match cipher(&header).ok_or(cocoon::Error::UnrecognizedFormat)? {
+        Cipher::ChaCha20Poly1305 => {
+            decrypt_segments::<ChaCha20Poly1305>(reader, writer, key, &header)
+        }
+        Cipher::Aes256Gcm => decrypt_segments::<Aes256Gcm>(reader, writer, key, &header),
+    }
+}
+
+/// Decrypt each segment after header, with given algorithm
+fn decrypt_segments<A>(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    key: &str,
+    header: &[u8],
+) -> Result<(), cocoon::Error>
+where
+    A: AeadInPlace + KeyInit + AeadCore<NonceSize = U12>,
+{
+    let (salt, nonce_prefix) = header[CIPHER_INDEX + 1..].split_at(SALT_SIZE);
+    let aead =
+        A::new_from_slice(&derive_key(key, salt)).map_err(|_| cocoon::Error::Cryptography)?;
+    let mut decryptor = DecryptorBE32::from_aead(aead, nonce_prefix.into());
+
+    let mut segment = vec![0; SEGMENT_SIZE + TAG_SIZE];
+    let mut next = vec![0; SEGMENT_SIZE + TAG_SIZE];
+    let mut length = read_full(&mut reader, &mut segment)?;
+
+    loop {
+        if length < TAG_SIZE {
+            return Err(cocoon::Error::TooShort);
+        }
+
+        // Segment is last if it is not full, or there is nothing after it
+        let next_length = if length < segment.len() {
+            0
+        } else {
+            read_full(&mut reader, &mut next)?
+        };
+
+        let payload = Payload {
+            msg: &segment[..length],
+            aad: header,
+        };
+
+        if next_length == 0 {
+            let plaintext = decryptor
+                .decrypt_last(payload)
+                .map_err(|_| cocoon::Error::Cryptography)?;
+            writer.write_all(&plaintext)?;
+            break;
+        }
+
+        let plaintext = decryptor
+            .decrypt_next(payload)
+            .map_err(|_| cocoon::Error::Cryptography)?;
+        writer.write_all(&plaintext)?;
+
+        std::mem::swap(&mut segment, &mut next);
+        length = next_length;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Derive encryption key from password and salt
+///
+/// Both ciphers use 256-bit keys
+fn derive_key(password: &str, salt: &[u8]) -> Key {
+    let mut key = Key::default();
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, KDF_ITERATIONS, &mut key);
+    key
+}
+
+/// Read until buffer is full, or reader has ended
+///
+/// Returns number of bytes read
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut length = 0;
+    while length < buffer.len() {
+        match reader.read(&mut buffer[length..]) {
+            Ok(0) => break,
+            Ok(count) => length += count,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(length)
+}
@@ -17,6 +17,7 @@ fn check_save_state() {
         path: None,
         contents: String::new(),
         saved: false,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), false);
     assert_eq!(file.is_changed(), false);
@@ -25,6 +26,7 @@ fn check_save_state() {
         path: None,
         contents: String::new(),
         saved: true,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), false);
     assert_eq!(file.is_changed(), false);
@@ -34,6 +36,7 @@ fn check_save_state() {
         path: None,
         contents: String::from("Some contents"),
         saved: false,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), false);
     assert_eq!(file.is_changed(), true);
@@ -42,6 +45,7 @@ fn check_save_state() {
         path: None,
         contents: String::from("Some contents"),
         saved: true,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), false);
     assert_eq!(file.is_changed(), true);
@@ -56,6 +60,7 @@ fn check_save_state() {
         path: Some(String::from("some/path")),
         contents: String::new(),
         saved: false,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), false);
     assert_eq!(file.is_changed(), true);
@@ -64,6 +69,7 @@ fn check_save_state() {
         path: Some(String::from("some/path")),
         contents: String::from("Some contents"),
         saved: false,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), false);
     assert_eq!(file.is_changed(), true);
@@ -73,6 +79,7 @@ fn check_save_state() {
         path: Some(String::from("some/path")),
         contents: String::new(),
         saved: true,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), true);
     assert_eq!(file.is_changed(), false);
@@ -81,7 +88,848 @@ fn check_save_state() {
         path: Some(String::from("some/path")),
         contents: String::from("Some contents"),
         saved: true,
+        ..Default::default()
     };
     assert_eq!(file.is_registered_and_saved(), true);
     assert_eq!(file.is_changed(), false);
 }
+
+#[test]
+fn save_and_open_with_metadata() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-metadata.enc");
+    let path = path.display().to_string();
+
+    let mut file = File {
+        contents: String::from("key = \"value\"\n"),
+        ..Default::default()
+    };
+    file.set_language(Language::Toml);
+    file.record_edit();
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.contents(), file.contents());
+    assert_eq!(opened.language(), Language::Toml);
+    assert!(!opened.undo_history().can_undo());
+
+    // Save with undo history
+    file.set_persist_undo(true);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+
+    let mut opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert!(opened.undo().is_some());
+    assert_eq!(opened.contents(), "");
+
+    // Save with version history
+    file.set_keep_versions(true);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    file.contents_mut().push_str("number = 1\n");
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    let versions = opened.versions().versions();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0].text().unwrap(), "key = \"value\"\n");
+
+    // Wrong key
+    assert!(File::open_path_and_decrypt(&path, "wrong key").is_err());
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn decode_plain_text() {
+    // Files from before metadata was added
+    let document = document::decode(b"Some contents".to_vec()).expect("Decode");
+    assert_eq!(document.contents, "Some contents");
+    assert_eq!(document.metadata, Metadata::default());
+
+    // Invalid utf8, without magic
+    assert!(document::decode(vec![0xC0, 0x00]).is_err());
+
+    // Round trip
+    let metadata = Metadata {
+        language: Language::Rust,
+        ..Default::default()
+    };
+    let bytes = document::encode("fn main() {}", &metadata, None, &Default::default(), &[])
+        .expect("Encode");
+    let document = document::decode(bytes).expect("Decode");
+    assert_eq!(document.contents, "fn main() {}");
+    assert_eq!(document.metadata, metadata);
+}
+
+#[test]
+fn change_while_saving() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-concurrent.enc");
+    let path = path.display().to_string();
+
+    let mut file = File::default();
+    file.set_path(&path);
+    *file.contents_mut() = String::from("Saved");
+
+    // Saved copy, as on another thread
+    let mut saving = file.clone();
+    saving
+        .save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    file.finish_save(saving.clone());
+    assert!(file.is_registered_and_saved());
+
+    // Edited while saving
+    *file.contents_mut() = String::from("Edited");
+    file.mark_as_unsaved();
+    let mut saving = file.clone();
+    file.contents_mut().push_str(" again");
+    saving
+        .save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    file.finish_save(saving);
+    assert!(!file.is_registered_and_saved());
+    assert!(file.is_changed());
+
+    // Attachment added while saving
+    let mut saving = file.clone();
+    file.add_attachment("image.png", vec![1, 2, 3]);
+    saving
+        .save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    file.finish_save(saving);
+    assert!(file.is_changed());
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn detect_changes_on_disk() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-disk.enc");
+    let path = path.display().to_string();
+
+    let mut file = File::default();
+    file.set_path(&path);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    assert!(!file.is_changed_on_disk());
+
+    // Another program writes to file
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    std::fs::write(&path, "other").expect("Write file");
+    assert!(file.is_changed_on_disk());
+
+    file.ignore_changes_on_disk();
+    assert!(!file.is_changed_on_disk());
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn lock_file() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-lock.enc");
+    let path = path.display().to_string();
+    let lock_path = format!("{}.lock", path);
+
+    // Locked by this program
+    let lock = FileLock::acquire(&path).unwrap().expect("Acquire lock");
+    assert!(std::path::Path::new(&lock_path).exists());
+    drop(lock);
+    assert!(!std::path::Path::new(&lock_path).exists());
+
+    // Locked by a program on another computer
+    let other = LockOwner {
+        host: String::from("other-host"),
+        pid: 1,
+        user: String::from("someone"),
+    };
+    std::fs::write(&lock_path, toml::to_string(&other).unwrap()).unwrap();
+    assert_eq!(FileLock::acquire(&path).unwrap().unwrap_err(), other);
+
+    // Invalid lock is replaced
+    std::fs::write(&lock_path, "invalid").unwrap();
+    let lock = FileLock::acquire(&path).unwrap().expect("Acquire lock");
+    assert!(lock.is_for(&path));
+    assert!(!lock.is_for("other.enc"));
+    assert!(std::fs::read_to_string(&lock_path)
+        .unwrap()
+        .contains(&std::process::id().to_string()));
+    drop(lock);
+    assert!(!std::path::Path::new(&lock_path).exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn open_read_only_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-read-only.enc");
+    let path = path.display().to_string();
+
+    File::default()
+        .save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    let file = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(file.read_only(), None);
+
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o400)).unwrap();
+    let file = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    // Root can write any file
+    if std::fs::OpenOptions::new().write(true).open(&path).is_err() {
+        assert_eq!(file.read_only(), Some(ReadOnly::NotWritable));
+    }
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[cfg(unix)]
+#[test]
+fn secure_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-permissions.enc");
+    let path = path.display().to_string();
+    let _ = std::fs::remove_file(&path);
+
+    let mut file = File::default();
+    file.set_path(&path);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(!file.has_insecure_permissions());
+
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    assert!(file.has_insecure_permissions());
+    file.fix_permissions().expect("Fix permissions");
+    assert!(!file.has_insecure_permissions());
+
+    // Permissions of existing file are kept when saving
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn stream_encryption() {
+    // Several segments, with a partial last segment
+    let data: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+
+    let mut encrypted = Vec::new();
+    stream::encrypt_stream(
+        data.as_slice(),
+        &mut encrypted,
+        "key",
+        Cipher::ChaCha20Poly1305,
+    )
+    .expect("Encrypt");
+    assert!(stream::is_stream(&encrypted));
+
+    let mut decrypted = Vec::new();
+    stream::decrypt_stream(encrypted.as_slice(), &mut decrypted, "key").expect("Decrypt");
+    assert_eq!(decrypted, data);
+
+    // Wrong key
+    assert!(stream::decrypt_stream(encrypted.as_slice(), io::sink(), "wrong key").is_err());
+
+    // Truncated at end of a segment
+    let truncated = &encrypted[..encrypted.len() - (200_000 - 2 * 65_536) - 16];
+    assert!(stream::decrypt_stream(truncated, io::sink(), "key").is_err());
+
+    // Tampered
+    let mut tampered = encrypted.clone();
+    tampered[100_000] ^= 1;
+    assert!(stream::decrypt_stream(tampered.as_slice(), io::sink(), "key").is_err());
+
+    // Empty data
+    let mut encrypted = Vec::new();
+    stream::encrypt_stream(io::empty(), &mut encrypted, "key", Cipher::ChaCha20Poly1305)
+        .expect("Encrypt");
+    let mut decrypted = Vec::new();
+    stream::decrypt_stream(encrypted.as_slice(), &mut decrypted, "key").expect("Decrypt");
+    assert!(decrypted.is_empty());
+}
+
+#[test]
+fn save_and_open_stream() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-stream.enc");
+    let path = path.display().to_string();
+    let output = format!("{}.txt", path);
+
+    let mut file = File {
+        contents: "line\n".repeat(50_000),
+        ..Default::default()
+    };
+    file.set_path(&path);
+    file.set_container(Container::Stream);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    assert!(!file.is_changed_on_disk());
+
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.contents(), file.contents());
+    assert_eq!(opened.container(), Container::Stream);
+    assert!(!opened.is_changed_on_disk());
+
+    // Decrypt contents without metadata
+    decrypt_file(&path, &output, "key").expect("Decrypt file");
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), *file.contents());
+
+    // Encrypt plain text, which opens without metadata
+    encrypt_file(&output, &path, "key", Cipher::ChaCha20Poly1305).expect("Encrypt file");
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.contents(), file.contents());
+
+    // Input is not destroyed, if it is also output
+    let encrypted = std::fs::read(&path).unwrap();
+    assert!(encrypt_file(&path, &path, "key", Cipher::ChaCha20Poly1305).is_err());
+    assert!(decrypt_file(&path, &path, "key").is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), encrypted);
+
+    // Output is not replaced, if stream is truncated
+    std::fs::write(&output, "Previous output").unwrap();
+    std::fs::write(&path, &encrypted[..encrypted.len() - 100]).unwrap();
+    assert!(decrypt_file(&path, &output, "key").is_err());
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "Previous output");
+    assert!(!std::path::Path::new(&format!("{}.tmp", output)).exists());
+
+    // Large files are refused by editor, but not by command line
+    std::fs::write(&path, &encrypted).unwrap();
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_len(MAX_EDITOR_STREAM_SIZE + 1)
+        .unwrap();
+    match File::open_path_and_decrypt(&path, "key") {
+        Err(cocoon::Error::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::FileTooLarge),
+        _ => panic!("Expected file to be too large"),
+    }
+
+    std::fs::remove_file(path).expect("Remove file");
+    std::fs::remove_file(output).expect("Remove file");
+}
+
+#[test]
+fn selectable_cipher() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-cipher.enc");
+    let path = path.display().to_string();
+
+    for container in [Container::Cocoon, Container::Stream] {
+        for cipher in Cipher::ALL {
+            let mut file = File {
+                contents: String::from("Some contents"),
+                ..Default::default()
+            };
+            file.set_container(container);
+            file.set_cipher(cipher);
+            file.save_to_path_encrypted(&path, "key")
+                .expect("Save file");
+
+            // Cipher is read from file
+            let encrypted = std::fs::read(&path).unwrap();
+            assert_eq!(Container::detect(&encrypted), container);
+            assert_eq!(detect_cipher(&encrypted, container), cipher);
+
+            let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+            assert_eq!(opened.contents(), file.contents());
+            assert_eq!(opened.container(), container);
+            assert_eq!(opened.cipher(), cipher);
+
+            assert!(File::open_path_and_decrypt(&path, "wrong key").is_err());
+        }
+    }
+
+    // Streams from before cipher could be chosen
+    let mut encrypted = Vec::new();
+    stream::encrypt_stream(&b"Old"[..], &mut encrypted, "key", Cipher::ChaCha20Poly1305)
+        .expect("Encrypt");
+    assert!(encrypted.starts_with(b"ETESTRM\x01"));
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn compressed_document() {
+    let contents = "2023-05-01 INFO Request handled\n".repeat(1000);
+    let metadata = Metadata {
+        compress: true,
+        ..Default::default()
+    };
+
+    let bytes =
+        document::encode(&contents, &metadata, None, &Default::default(), &[]).expect("Encode");
+    assert!(bytes.len() < contents.len() / 10);
+    let document = document::decode(bytes.clone()).expect("Decode");
+    assert_eq!(document.contents, contents);
+    assert_eq!(document.metadata, metadata);
+
+    // Decompressed data larger than limit
+    let compressed = &bytes[b"\xFFETEZ".len()..];
+    assert!(document::decompress(compressed, 1000).is_err());
+    assert!(document::decompress(compressed, 100_000).is_ok());
+}
+
+#[test]
+fn padded_data() {
+    let bytes = b"secret".to_vec();
+    assert_eq!(padding::pad(bytes.clone(), Padding::None), bytes);
+
+    let padded = padding::pad(bytes.clone(), Padding::PowerOfTwo);
+    assert_eq!(padded.len(), 256);
+    assert_eq!(padding::unpad(padded).unwrap(), bytes);
+
+    let long = vec![1; 5000];
+    let padded = padding::pad(long.clone(), Padding::Bucket(4096));
+    assert_eq!(padded.len(), 8192);
+    assert_eq!(padding::unpad(padded).unwrap(), long);
+
+    // Unpadded data is unchanged
+    assert_eq!(padding::unpad(bytes.clone()).unwrap(), bytes);
+
+    // Length longer than data
+    let mut padded = padding::pad(bytes, Padding::PowerOfTwo);
+    padded[6] = 0xFF;
+    assert!(padding::unpad(padded).is_err());
+}
+
+#[test]
+fn save_and_open_attachments() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-attachments.enc");
+    let path = path.display().to_string();
+
+    let mut file = File::default();
+    file.add_attachment("key.bin", vec![0, 1, 2, 255]);
+    file.add_attachment("key.bin", vec![3]);
+    assert_eq!(file.attachments()[1].name, "key.bin (2)");
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+
+    let mut opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.attachments(), file.attachments());
+    // Hash is computed again when opened
+    assert_eq!(opened.attachments()[0].hash(), file.attachments()[0].hash());
+    assert_ne!(
+        opened.attachments()[0].hash(),
+        opened.attachments()[1].hash()
+    );
+
+    // Export without encryption
+    let export_path = format!("{}.bin", path);
+    opened
+        .export_attachment(0, &export_path)
+        .expect("Export attachment");
+    assert_eq!(std::fs::read(&export_path).unwrap(), [0, 1, 2, 255]);
+    assert!(!permissions::is_insecure(&export_path).unwrap());
+    assert!(opened.export_attachment(5, &export_path).is_err());
+    std::fs::remove_file(export_path).expect("Remove file");
+
+    opened.remove_attachment(0);
+    assert_eq!(opened.attachments().len(), 1);
+    assert!(!opened.is_registered_and_saved());
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn import_and_export_plaintext() {
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-import.txt");
+    let path = path.display().to_string();
+
+    std::fs::write(&path, "# Notes\n").expect("Write file");
+    let mut file = File::import_plaintext(&path).expect("Import file");
+    assert_eq!(file.contents(), "# Notes\n");
+    assert_eq!(file.path(), None);
+    assert!(file.is_changed());
+    assert!(!file.undo_history().can_undo());
+
+    file.contents_mut().push_str("More\n");
+    file.export_decrypted(&path).expect("Export file");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Notes\nMore\n");
+
+    remove_securely(&path).expect("Remove file");
+    assert!(!std::path::Path::new(&path).exists());
+}
+
+#[test]
+fn armored_text() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    let text = armor::armor(&bytes);
+    assert!(armor::is_armored(text.as_bytes()));
+    assert!(text.lines().all(|line| line.len() <= 64));
+
+    // Text around markers, and changed line endings
+    let pasted = format!("Hi,\r\n\r\n{}\r\nThanks", text.replace('\n', "\r\n"));
+    assert_eq!(armor::dearmor(&pasted).unwrap(), bytes);
+    assert!(armor::dearmor("no markers").is_err());
+
+    let mut file = File {
+        contents: String::from("secret"),
+        ..Default::default()
+    };
+    file.set_container(Container::Stream);
+    let armored = file.to_armored("key").expect("Armor file");
+    let opened = File::from_armored(&armored, "key").expect("Open armored text");
+    assert_eq!(opened.contents(), "secret");
+    assert_eq!(opened.container(), Container::Stream);
+    assert!(File::from_armored(&armored, "wrong key").is_err());
+
+    // Armored file on disk is saved as armored text again
+    let path = std::env::temp_dir().join("encrypted-text-editor-test-armored.asc");
+    let path = path.display().to_string();
+    std::fs::write(&path, &armored).expect("Write file");
+    let mut opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert!(opened.is_armored());
+    assert!(!opened.is_changed_on_disk());
+    opened
+        .save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    assert!(armor::is_armored(&std::fs::read(&path).unwrap()));
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn age_files() {
+    use ::age::x25519;
+
+    // Recipient, as from `age-keygen`
+    let identity = x25519::Identity::generate();
+    let mut encrypted = Vec::new();
+    age::encrypt(
+        &b"secret"[..],
+        &mut encrypted,
+        age::AgeKey::Identity(&identity),
+    )
+    .expect("Encrypt");
+    assert_eq!(Container::detect(&encrypted), Container::AgeX25519);
+
+    let mut decrypted = Vec::new();
+    age::decrypt(
+        encrypted.as_slice(),
+        &mut decrypted,
+        age::AgeKey::Identity(&identity),
+    )
+    .expect("Decrypt");
+    assert_eq!(decrypted, b"secret");
+
+    let other = x25519::Identity::generate();
+    assert!(age::decrypt(
+        encrypted.as_slice(),
+        io::sink(),
+        age::AgeKey::Identity(&other)
+    )
+    .is_err());
+    assert!(age::decrypt(
+        encrypted.as_slice(),
+        io::sink(),
+        age::AgeKey::Passphrase("key")
+    )
+    .is_err());
+
+    let text = format!("# public key: {}\n{}\n", identity.to_public(), {
+        use ::age::secrecy::ExposeSecret;
+        identity.to_string().expose_secret().clone()
+    });
+    let parsed = age::parse_identity(&text).expect("Parse identity");
+    assert_eq!(
+        parsed.to_public().to_string(),
+        identity.to_public().to_string()
+    );
+
+    // Passphrase
+    let path = std::env::temp_dir().join("encrypted-text-editor-test.age");
+    let path = path.display().to_string();
+
+    let mut file = File {
+        contents: String::from("secret"),
+        ..Default::default()
+    };
+    file.set_container(Container::AgePassphrase);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    assert!(std::fs::read(&path)
+        .unwrap()
+        .starts_with(b"age-encryption.org/v1\n"));
+
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.contents(), "secret");
+    assert_eq!(opened.container(), Container::AgePassphrase);
+    assert_eq!(
+        File::detect_container(&path).unwrap(),
+        Container::AgePassphrase
+    );
+
+    // Only text is saved, so file can be read by `age -d`
+    let decryptor = match ::age::Decryptor::new(std::fs::File::open(&path).unwrap()) {
+        Ok(::age::Decryptor::Passphrase(decryptor)) => decryptor,
+        _ => panic!("Expected file with passphrase"),
+    };
+    let mut decrypted = String::new();
+    decryptor
+        .decrypt(
+            &::age::secrecy::SecretString::new(String::from("key")),
+            None,
+        )
+        .expect("Decrypt")
+        .read_to_string(&mut decrypted)
+        .unwrap();
+    assert_eq!(decrypted, "secret");
+
+    // Text is not padded or compressed, as other programs would not read it
+    file.set_padding(Padding::PowerOfTwo);
+    file.set_compress(true);
+    assert!(!file.has_data_not_in_format());
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    let mut decrypted = Vec::new();
+    age::decrypt(
+        std::fs::File::open(&path).unwrap(),
+        &mut decrypted,
+        age::AgeKey::Passphrase("key"),
+    )
+    .expect("Decrypt");
+    assert_eq!(decrypted, b"secret");
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.padding(), Padding::None);
+    assert!(!opened.compress());
+    file.set_padding(Padding::None);
+    file.set_compress(false);
+
+    // Other data is not lost silently
+    file.add_attachment("image.png", vec![1, 2, 3]);
+    assert!(file.has_data_not_in_format());
+    assert!(file.save_to_path_encrypted(&path, "key").is_err());
+    file.remove_attachment(0);
+    file.set_keep_versions(true);
+    assert!(file.has_data_not_in_format());
+    assert!(file.save_to_path_encrypted(&path, "key").is_err());
+
+    // Saved file is kept when saving fails
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.contents(), "secret");
+    assert!(!std::path::Path::new(&format!("{}{}", path, TEMP_EXTENSION)).exists());
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn pgp_messages() {
+    // From `gpg --symmetric --armor`, with passphrase `key`
+    let from_gpg = "-----BEGIN PGP MESSAGE-----
+
+jA0ECQMC+UwZDf3Xce3/0ksBnneLa4jkvU8cho20FIaPr/t66wZ9guPPeQ2OXF5E
+tPZrxIIcmEfqzAJbItFjlol8bU5fiSXsivpjAS/YAtuHm7mk/8eoPvH4WnY=
+=DOug
+-----END PGP MESSAGE-----
+";
+    assert_eq!(Container::detect(from_gpg.as_bytes()), Container::Pgp);
+    let file = File::from_armored(from_gpg, "key").expect("Open message");
+    assert_eq!(file.contents(), "Hello from gpg\n");
+    assert!(File::from_armored(from_gpg, "wrong key").is_err());
+
+    // Binary message, as `.gpg` file
+    let path = std::env::temp_dir().join("encrypted-text-editor-test.gpg");
+    let path = path.display().to_string();
+
+    let mut file = File {
+        contents: String::from("secret"),
+        ..Default::default()
+    };
+    file.set_container(Container::Pgp);
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    assert_eq!(
+        Container::detect(&std::fs::read(&path).unwrap()),
+        Container::Pgp
+    );
+
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.contents(), "secret");
+    assert_eq!(opened.container(), Container::Pgp);
+    assert!(!opened.is_armored());
+
+    // Literal data is only text, so file can be read by `gpg -d`
+    let decrypted = pgp::decrypt(std::fs::File::open(&path).unwrap(), "key").expect("Decrypt");
+    assert_eq!(decrypted, b"secret");
+
+    // Padding, compression and language are not saved
+    file.set_padding(Padding::PowerOfTwo);
+    file.set_compress(true);
+    file.set_language(Language::Toml);
+    assert!(!file.has_data_not_in_format());
+    file.save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    let decrypted = pgp::decrypt(std::fs::File::open(&path).unwrap(), "key").expect("Decrypt");
+    assert_eq!(decrypted, b"secret");
+    let opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert_eq!(opened.padding(), Padding::None);
+    assert!(!opened.compress());
+    assert_eq!(opened.language(), Language::default());
+
+    // Decompressed data is limited in size
+    assert!(document::read_limited(&[0u8; 10][..], 5).is_err());
+    assert_eq!(document::read_limited(&[0u8; 5][..], 5).unwrap().len(), 5);
+
+    // Armored message, as `.asc` file
+    std::fs::write(&path, from_gpg).expect("Write file");
+    let mut opened = File::open_path_and_decrypt(&path, "key").expect("Open file");
+    assert!(opened.is_armored());
+    opened
+        .save_to_path_encrypted(&path, "key")
+        .expect("Save file");
+    assert!(pgp::is_armored(&std::fs::read(&path).unwrap()));
+
+    std::fs::remove_file(path).expect("Remove file");
+}
+
+#[test]
+fn migrate_legacy_files() {
+    use ::age::x25519;
+
+    let directory = std::env::temp_dir().join("encrypted-text-editor-test-migrate");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(directory.join("nested")).unwrap();
+    let path = |name: &str| directory.join(name).display().to_string();
+
+    // Legacy files, in two formats, and a file with another key
+    for (name, container, key) in [
+        ("a.enc", Container::Cocoon, "legacy"),
+        ("nested/b.enc", Container::Stream, "legacy"),
+        ("c.enc", Container::Cocoon, "other"),
+    ] {
+        let mut file = File {
+            contents: format!("Contents of {}", name),
+            ..Default::default()
+        };
+        file.set_container(container);
+        file.save_to_path_encrypted(&path(name), key)
+            .expect("Save file");
+    }
+    std::fs::write(path("ignored.txt"), "Not encrypted").unwrap();
+
+    // File with data which age files cannot save
+    let mut file = File::default();
+    file.add_attachment("image.png", vec![1, 2, 3]);
+    file.save_to_path_encrypted(&path("d.enc"), "legacy")
+        .expect("Save file");
+
+    // Padded file with undo history
+    let mut file = File {
+        contents: String::from("Padded"),
+        ..Default::default()
+    };
+    file.set_padding(Padding::PowerOfTwo);
+    file.set_persist_undo(true);
+    assert_eq!(file.data_not_in_text_only(), ["undo history", "padding"]);
+    file.save_to_path_encrypted(&path("e.enc"), "legacy")
+        .expect("Save file");
+
+    // Existing backup is not overwritten
+    std::fs::write(path("a.enc.legacy.bak"), "Existing backup").unwrap();
+
+    let target = MigrationTarget::Password(String::from("password"));
+    let report = migrate_directory(
+        &directory.display().to_string(),
+        "enc",
+        "legacy",
+        &target,
+        true,
+    )
+    .expect("Migrate directory");
+    assert_eq!(report.migrated, [path("a.enc"), path("nested/b.enc")]);
+    assert_eq!(report.failed.len(), 3);
+    assert_eq!(report.failed[0].0, path("c.enc"));
+    assert_eq!(report.failed[1].0, path("d.enc"));
+    assert!(report.failed[1].1.contains("attachments"));
+    assert_eq!(report.failed[2].0, path("e.enc"));
+    assert!(report.failed[2].1.contains("undo history, padding"));
+
+    // Files which failed are not changed
+    let opened = File::open_path_and_decrypt(path("e.enc"), "legacy").expect("Open file");
+    assert_eq!(opened.padding(), Padding::PowerOfTwo);
+    assert!(opened.persist_undo());
+    assert!(!std::path::Path::new(&path("e.enc.legacy.bak")).exists());
+
+    // Migrated files are age files, and originals are kept
+    // Editor asks for passphrase, as legacy key does not open file
+    assert!(File::detect_container(&path("nested/b.enc"))
+        .unwrap()
+        .asks_passphrase());
+    assert!(matches!(
+        File::open_path_and_decrypt(path("nested/b.enc"), "legacy"),
+        Err(cocoon::Error::Cryptography)
+    ));
+    let opened = File::open_path_and_decrypt(path("nested/b.enc"), "password").expect("Open file");
+    assert_eq!(opened.container(), Container::AgePassphrase);
+    assert_eq!(opened.contents(), "Contents of nested/b.enc");
+
+    let mut decrypted = Vec::new();
+    age::decrypt(
+        std::fs::File::open(path("nested/b.enc")).unwrap(),
+        &mut decrypted,
+        age::AgeKey::Passphrase("password"),
+    )
+    .expect("Decrypt");
+    assert_eq!(decrypted, b"Contents of nested/b.enc");
+
+    assert_eq!(
+        std::fs::read_to_string(path("a.enc.legacy.bak")).unwrap(),
+        "Existing backup"
+    );
+    let backup =
+        File::open_path_and_decrypt(path("a.enc.legacy.2.bak"), "legacy").expect("Open backup");
+    assert_eq!(backup.contents(), "Contents of a.enc");
+
+    // Migrating again does not change migrated files
+    let report = migrate_directory(
+        &directory.display().to_string(),
+        "enc",
+        "legacy",
+        &target,
+        false,
+    )
+    .expect("Migrate directory");
+    assert!(report.migrated.is_empty());
+    assert_eq!(report.failed.len(), 5);
+
+    let report_path = report.write().expect("Write report");
+    assert!(std::fs::read_to_string(report_path)
+        .unwrap()
+        .contains("0 migrated, 5 failed"));
+
+    // Recipients
+    let identity = x25519::Identity::generate();
+    let target = MigrationTarget::recipients(&identity.to_public().to_string(), false).unwrap();
+    let report = migrate_directory(
+        &directory.display().to_string(),
+        "bak",
+        "legacy",
+        &target,
+        false,
+    )
+    .expect("Migrate directory");
+    assert_eq!(
+        report.migrated,
+        [path("a.enc.legacy.2.bak"), path("nested/b.enc.legacy.bak")]
+    );
+
+    let mut decrypted = Vec::new();
+    let encrypted = std::fs::read(path("a.enc.legacy.2.bak")).unwrap();
+    assert_eq!(Container::detect(&encrypted), Container::AgeX25519);
+    age::decrypt(
+        encrypted.as_slice(),
+        &mut decrypted,
+        age::AgeKey::Identity(&identity),
+    )
+    .expect("Decrypt");
+    assert_eq!(decrypted, b"Contents of a.enc");
+    assert!(MigrationTarget::recipients("not-a-key", false).is_err());
+
+    std::fs::remove_dir_all(directory).expect("Remove directory");
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests;
+
+use std::{ops::Range, sync::Arc, sync::OnceLock};
+
+use eframe::{
+    egui::{self, util::cache::ComputerMut},
+    epaint::Color32,
+};
+use serde::{Deserialize, Serialize};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::{SyntaxDefinition, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// TOML is not included in the default syntaxes
+const TOML_SYNTAX: &str = include_str!("../../assets/syntaxes/TOML.sublime-syntax");
+
+/// Language of file contents, used for syntax highlighting
+///
+/// Stored in file metadata, as file extension is always the same
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    PlainText,
+    Markdown,
+    Toml,
+    Yaml,
+    Json,
+    Shell,
+    Rust,
+}
+
+impl Language {
+    /// Every language, in order of display
+    pub const ALL: &'static [Self] = &[
+        Self::PlainText,
+        Self::Markdown,
+        Self::Toml,
+        Self::Yaml,
+        Self::Json,
+        Self::Shell,
+        Self::Rust,
+    ];
+
+    /// Human-readable name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PlainText => "Plain text",
+            Self::Markdown => "Markdown",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Json => "JSON",
+            Self::Shell => "Shell",
+            Self::Rust => "Rust",
+        }
+    }
+
+    /// Get syntax definition
+    ///
+    /// `None` for plain text
+    fn syntax(self) -> Option<&'static SyntaxReference> {
+        let name = match self {
+            Self::PlainText => return None,
+            Self::Markdown => "Markdown",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Json => "JSON",
+            Self::Shell => "Bourne Again Shell (bash)",
+            Self::Rust => "Rust",
+        };
+        syntax_set().find_syntax_by_name(name)
+    }
+}
+
+/// Span of text with a color
+pub type Highlights = Arc<Vec<(Range<usize>, Color32)>>;
+
+/// Get color of each span of text, for language
+///
+/// Results are cached while the same text is highlighted each frame
+pub fn highlight(ctx: &egui::Context, text: &str, language: Language) -> Highlights {
+    type HighlightCache = egui::util::cache::FrameCache<Highlights, Highlighter>;
+
+    let dark_mode = ctx.style().visuals.dark_mode;
+    ctx.memory_mut(|memory| {
+        memory
+            .caches
+            .cache::<HighlightCache>()
+            .get((text, language, dark_mode))
+    })
+}
+
+/// Computes highlights for cache
+#[derive(Default)]
+struct Highlighter;
+
+impl ComputerMut<(&str, Language, bool), Highlights> for Highlighter {
+    fn compute(&mut self, (text, language, dark_mode): (&str, Language, bool)) -> Highlights {
+        Arc::new(highlight_uncached(text, language, dark_mode).unwrap_or_default())
+    }
+}
+
+/// Get color of each span of text
+///
+/// Returns `None` if language has no syntax, or text could not be parsed
+fn highlight_uncached(
+    text: &str,
+    language: Language,
+    dark_mode: bool,
+) -> Option<Vec<(Range<usize>, Color32)>> {
+    let syntax = language.syntax()?;
+    let mut highlighter = HighlightLines::new(syntax, theme(dark_mode));
+
+    let mut spans = Vec::new();
+    let mut position = 0;
+
+    for line in LinesWithEndings::from(text) {
+        for (style, span) in highlighter.highlight_line(line, syntax_set()).ok()? {
+            let color = style.foreground;
+            let color = Color32::from_rgba_unmultiplied(color.r, color.g, color.b, color.a);
+
+            spans.push((position..position + span.len(), color));
+            position += span.len();
+        }
+    }
+
+    Some(spans)
+}
+
+/// Get default syntaxes, with any extra bundled syntaxes
+///
+/// This is slow, so is only loaded once, when first used
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+
+    SYNTAX_SET.get_or_init(|| {
+        let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+        builder.add(
+            SyntaxDefinition::load_from_str(TOML_SYNTAX, true, None)
+                .expect("Bundled TOML syntax should be valid"),
+        );
+        builder.build()
+    })
+}
+
+/// Get color theme, matching light or dark mode
+fn theme(dark_mode: bool) -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+
+    let themes = &THEME_SET.get_or_init(ThemeSet::load_defaults).themes;
+    if dark_mode {
+        &themes["base16-ocean.dark"]
+    } else {
+        &themes["InspiredGitHub"]
+    }
+}
@@ -0,0 +1,34 @@
+use super::*;
+
+#[test]
+fn every_language_has_syntax() {
+    for &language in Language::ALL {
+        if language == Language::PlainText {
+            assert!(language.syntax().is_none());
+        } else {
+            assert!(
+                language.syntax().is_some(),
+                "Missing syntax for {}",
+                language.name()
+            );
+        }
+    }
+}
+
+#[test]
+fn highlights_cover_text() {
+    let text = "[table]\nkey = \"value\" # comment\nnumber = 1.5\n";
+
+    let spans = highlight_uncached(text, Language::Toml, true).expect("Highlight TOML");
+
+    // Spans should be in order, with no gaps
+    let mut position = 0;
+    for (range, _) in &spans {
+        assert_eq!(range.start, position);
+        position = range.end;
+    }
+    assert_eq!(position, text.len());
+
+    // Should use more than one color
+    assert!(spans.iter().any(|(_, color)| *color != spans[0].1));
+}
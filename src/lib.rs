@@ -7,13 +7,37 @@ mod app;
 mod attempt;
 /// Wrapper for `Sender` and `Receiver` types in `std::sync::mpsc`
 mod channel;
+/// Encrypt and decrypt files from command line
+mod cli;
+/// Named commands, and keybinds to run them
+mod commands;
 /// Handle file input/output and save state
 mod file;
+/// Syntax highlighting of file contents
+mod highlight;
+/// List of recently opened files
+mod recent;
+/// Find and replace text, with options such as regex
+mod search;
+/// User preferences, persisted in config directory
+mod settings;
+/// Count characters, words, and lines of text
+mod stats;
+/// Undo and redo history of text
+mod undo;
+/// Compressed text of file at previous saves
+mod versions;
 
 use std::path::PathBuf;
 
-pub use crate::app::App;
-use crate::{attempt::Attempt, channel::Channel, file::File};
+pub use crate::{app::App, cli::run_cli, settings::Settings};
+use crate::{
+    attempt::Attempt,
+    channel::Channel,
+    commands::{Command, Keybinds},
+    file::File,
+    recent::RecentFiles,
+};
 
 /// Cryption key which every file uses
 ///
@@ -34,11 +58,24 @@ fn get_start_dir() -> Option<PathBuf> {
     None
 }
 
+/// Get directory to store app data, such as settings and recent files
+fn get_config_dir() -> Option<PathBuf> {
+    dirs_next::config_dir().map(|dir| dir.join("encrypted-text-editor"))
+}
+
 /// Create simple file open/save dialog with `rfd`
-fn file_dialog() -> rfd::FileDialog {
-    let dialog = rfd::FileDialog::new().add_filter("Encrypted file", &["enc"]);
+///
+/// Opens in `start_dir` if given, otherwise uses default directory (See `get_start_dir`)
+///
+/// Filters files by `extension`
+fn file_dialog(start_dir: Option<PathBuf>, extension: &str) -> rfd::FileDialog {
+    let mut dialog = rfd::FileDialog::new();
+
+    if !extension.is_empty() {
+        dialog = dialog.add_filter("Encrypted file", &[extension]);
+    }
 
-    if let Some(dir) = get_start_dir() {
+    if let Some(dir) = start_dir.or_else(get_start_dir) {
         dialog.set_directory(dir)
     } else {
         dialog
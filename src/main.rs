@@ -2,15 +2,23 @@
 
 use eframe::egui;
 
-use encrypted_text_editor::App;
+use encrypted_text_editor::{run_cli, App, Settings};
 
 // Start egui/eframe app
 fn main() -> Result<(), eframe::Error> {
     tracing_subscriber::fmt::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // Run command instead of opening editor, if any arguments were given
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(run_cli(&args));
+    }
+
+    let settings = Settings::load();
+
     let options = eframe::NativeOptions {
         drag_and_drop_support: true,
-        initial_window_size: Some(egui::vec2(600.0, 400.0)),
+        initial_window_size: Some(egui::vec2(settings.window_width, settings.window_height)),
         ..Default::default()
     };
 
@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use cocoon::Cocoon;
+
+use crate::{get_config_dir, KEY};
+
+/// Maximum amount of paths to remember
+const MAX_RECENT_FILES: usize = 10;
+
+/// Name of file in config directory, which stores the list if it is encrypted
+const RECENT_FILES_FILENAME: &str = "recent.enc";
+
+/// Name of file in config directory, which stores the list if it is not encrypted
+const PLAIN_RECENT_FILES_FILENAME: &str = "recent.txt";
+
+/// List of recently opened files, most recent first
+#[derive(Default)]
+pub struct RecentFiles {
+    paths: Vec<String>,
+    /// Whether list is encrypted when saved, as paths may be sensitive
+    encrypted: bool,
+    /// Directory which list is saved in
+    ///
+    /// `None` if list is not saved, such as if there is no config directory
+    dir: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Load list from config directory
+    ///
+    /// Returns empty list if file does not exist, or could not be read
+    pub fn load(encrypted: bool) -> Self {
+        Self::load_from(get_config_dir(), encrypted)
+    }
+
+    /// Load list from directory
+    ///
+    /// Returns empty list if file does not exist, or could not be read
+    fn load_from(dir: Option<PathBuf>, encrypted: bool) -> Self {
+        let mut recent_files = Self {
+            paths: Vec::new(),
+            encrypted,
+            dir,
+        };
+
+        // Ignore list if invalid
+        if let Some(text) = recent_files.read() {
+            recent_files.paths = text
+                .lines()
+                .filter(|line| !line.is_empty())
+                .take(MAX_RECENT_FILES)
+                .map(String::from)
+                .collect();
+        }
+        recent_files
+    }
+
+    /// Read text of saved list, decrypting if encrypted
+    fn read(&self) -> Option<String> {
+        let path = self.path()?;
+
+        let bytes = if self.encrypted {
+            let mut file = fs::File::open(path).ok()?;
+            Cocoon::new(KEY.as_bytes()).parse(&mut file).ok()?
+        } else {
+            fs::read(path).ok()?
+        };
+        String::from_utf8(bytes).ok()
+    }
+
+    /// Save list to config directory
+    ///
+    /// Errors are printed to stderr, but otherwise ignored
+    fn save(&self) {
+        let Some(path) = self.path() else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            if let Err(error) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create config directory: {:?}", error);
+                return;
+            }
+        }
+
+        let mut file = match fs::File::create(path) {
+            Ok(file) => file,
+            Err(error) => {
+                eprintln!("Failed to save recent files: {:?}", error);
+                return;
+            }
+        };
+
+        let bytes = self.paths.join("\n").into_bytes();
+        if self.encrypted {
+            if let Err(error) = Cocoon::new(KEY.as_bytes()).dump(bytes, &mut file) {
+                eprintln!("Failed to save recent files: {:?}", error);
+            }
+        } else if let Err(error) = file.write_all(&bytes) {
+            eprintln!("Failed to save recent files: {:?}", error);
+        }
+    }
+
+    /// Get path of file which stores list, depending on whether it is encrypted
+    fn path(&self) -> Option<PathBuf> {
+        let filename = if self.encrypted {
+            RECENT_FILES_FILENAME
+        } else {
+            PLAIN_RECENT_FILES_FILENAME
+        };
+        self.dir.as_ref().map(|dir| dir.join(filename))
+    }
+
+    /// Change whether list is encrypted, and save list
+    ///
+    /// List saved in previous format is removed, so paths are not left in plain text
+    pub fn set_encrypted(&mut self, encrypted: bool) {
+        if self.encrypted == encrypted {
+            return;
+        }
+
+        if let Some(path) = self.path() {
+            if let Err(error) = fs::remove_file(path) {
+                if error.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Failed to remove recent files: {:?}", error);
+                }
+            }
+        }
+
+        self.encrypted = encrypted;
+        self.save();
+    }
+
+    /// Get list of paths, most recent first
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Returns `true` if no files are remembered
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Move path to top of list, and save list
+    pub fn add(&mut self, path: &str) {
+        self.paths.retain(|existing| existing != path);
+        self.paths.insert(0, path.to_string());
+        self.paths.truncate(MAX_RECENT_FILES);
+        self.save();
+    }
+
+    /// Remove path from list, and save list
+    pub fn remove(&mut self, path: &str) {
+        self.paths.retain(|existing| existing != path);
+        self.save();
+    }
+
+    /// Remove all paths, and save list
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.save();
+    }
+
+    /// Get directory of most recent file
+    ///
+    /// Used as start directory of file dialogs
+    pub fn last_dir(&self) -> Option<PathBuf> {
+        self.paths
+            .first()
+            .and_then(|path| Path::new(path).parent())
+            .filter(|dir| dir.is_dir())
+            .map(Path::to_path_buf)
+    }
+}
@@ -0,0 +1,84 @@
+use super::*;
+
+#[test]
+fn add_recent_files() {
+    // Not saved, as there is no directory
+    let mut recent_files = RecentFiles::default();
+    assert!(recent_files.is_empty());
+
+    recent_files.add("a.enc");
+    recent_files.add("b.enc");
+    assert_eq!(recent_files.paths(), ["b.enc", "a.enc"]);
+
+    // Adding again moves path to top, without duplicating it
+    recent_files.add("a.enc");
+    assert_eq!(recent_files.paths(), ["a.enc", "b.enc"]);
+
+    recent_files.remove("b.enc");
+    assert_eq!(recent_files.paths(), ["a.enc"]);
+
+    recent_files.clear();
+    assert!(recent_files.is_empty());
+}
+
+#[test]
+fn recent_files_are_limited() {
+    let mut recent_files = RecentFiles::default();
+    for index in 0..MAX_RECENT_FILES + 5 {
+        recent_files.add(&format!("{}.enc", index));
+    }
+
+    assert_eq!(recent_files.paths().len(), MAX_RECENT_FILES);
+    // Oldest paths are removed
+    assert_eq!(
+        recent_files.paths()[0],
+        format!("{}.enc", MAX_RECENT_FILES + 4)
+    );
+    assert!(!recent_files.paths().contains(&String::from("0.enc")));
+}
+
+#[test]
+fn last_dir_of_recent_files() {
+    let dir = std::env::temp_dir();
+
+    let mut recent_files = RecentFiles::default();
+    assert_eq!(recent_files.last_dir(), None);
+
+    recent_files.add(&dir.join("a.enc").display().to_string());
+    assert_eq!(recent_files.last_dir(), Some(dir.clone()));
+
+    // Directory of most recent file must exist
+    recent_files.add(&dir.join("missing").join("b.enc").display().to_string());
+    assert_eq!(recent_files.last_dir(), None);
+}
+
+#[test]
+fn save_recent_files() {
+    let dir = std::env::temp_dir().join("encrypted-text-editor-test-recent");
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut recent_files = RecentFiles::load_from(Some(dir.clone()), true);
+    recent_files.add("secret.enc");
+
+    // Encrypted list does not contain paths in plain text
+    let bytes = fs::read(dir.join(RECENT_FILES_FILENAME)).unwrap();
+    assert!(!String::from_utf8_lossy(&bytes).contains("secret.enc"));
+    let loaded = RecentFiles::load_from(Some(dir.clone()), true);
+    assert_eq!(loaded.paths(), ["secret.enc"]);
+
+    // Changing format replaces file
+    recent_files.set_encrypted(false);
+    assert!(!dir.join(RECENT_FILES_FILENAME).exists());
+    assert_eq!(
+        fs::read_to_string(dir.join(PLAIN_RECENT_FILES_FILENAME)).unwrap(),
+        "secret.enc"
+    );
+    let loaded = RecentFiles::load_from(Some(dir.clone()), false);
+    assert_eq!(loaded.paths(), ["secret.enc"]);
+
+    recent_files.set_encrypted(true);
+    assert!(!dir.join(PLAIN_RECENT_FILES_FILENAME).exists());
+    assert!(dir.join(RECENT_FILES_FILENAME).exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests;
+
+use std::ops::Range;
+
+use regex::{Captures, Regex, RegexBuilder};
+
+/// Options for finding text
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct SearchOptions {
+    /// Whether uppercase and lowercase letters are different
+    pub case_sensitive: bool,
+    /// Whether matches must be surrounded by word boundaries
+    pub whole_word: bool,
+    /// Whether query is a regular expression, instead of literal text
+    pub regex: bool,
+}
+
+/// Compiled search query
+pub struct Search {
+    pattern: Regex,
+    /// Whether replacement text can use capture groups, such as `$1`
+    expand_replacement: bool,
+}
+
+impl Search {
+    /// Compile query with options
+    ///
+    /// Returns `None` if query is empty
+    ///
+    /// Returns error if query is an invalid regular expression
+    pub fn new(query: &str, options: SearchOptions) -> Option<Result<Self, regex::Error>> {
+        if query.is_empty() {
+            return None;
+        }
+
+        // Literal text is escaped, so both modes use the same regex engine
+        let mut pattern = if options.regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let pattern = RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build();
+
+        Some(pattern.map(|pattern| Self {
+            pattern,
+            expand_replacement: options.regex,
+        }))
+    }
+
+    /// Get byte ranges of all non-empty matches in text
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        self.pattern
+            .find_iter(text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// Replace a single match, at given byte range
+    ///
+    /// Returns `None` if range is no longer a match (text has changed)
+    pub fn replace_at(&self, text: &str, range: Range<usize>, replacement: &str) -> Option<String> {
+        let captures = self.pattern.captures_at(text, range.start)?;
+        let found = captures.get(0)?;
+        if found.range() != range {
+            return None;
+        }
+
+        let mut text = text.to_string();
+        text.replace_range(range, &self.replacement(&captures, replacement));
+        Some(text)
+    }
+
+    /// Replace all matches in text
+    ///
+    /// Returns new text, and amount of replaced matches
+    pub fn replace_all(&self, text: &str, replacement: &str) -> (String, usize) {
+        let mut count = 0;
+
+        let text = self.pattern.replace_all(text, |captures: &Captures| {
+            // Empty matches are not found, so should not be replaced
+            if captures[0].is_empty() {
+                return String::new();
+            }
+            count += 1;
+            self.replacement(captures, replacement)
+        });
+
+        (text.into_owned(), count)
+    }
+
+    /// Get text to replace a match with
+    ///
+    /// Expands capture groups if query is a regular expression
+    fn replacement(&self, captures: &Captures, replacement: &str) -> String {
+        if self.expand_replacement {
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            expanded
+        } else {
+            replacement.to_string()
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+/// Find all matches, and return matched text
+fn find<'a>(text: &'a str, query: &str, options: SearchOptions) -> Vec<&'a str> {
+    let search = Search::new(query, options)
+        .expect("Non-empty query")
+        .expect("Valid query");
+
+    search
+        .find_all(text)
+        .into_iter()
+        .map(|range| &text[range])
+        .collect()
+}
+
+#[test]
+fn find_with_options() {
+    let text = "Cat cat concatenate CAT";
+
+    // Default: case insensitive, any position
+    let options = SearchOptions::default();
+    assert_eq!(find(text, "cat", options), ["Cat", "cat", "cat", "CAT"]);
+
+    // Case sensitive
+    let options = SearchOptions {
+        case_sensitive: true,
+        ..Default::default()
+    };
+    assert_eq!(find(text, "cat", options), ["cat", "cat"]);
+
+    // Whole word
+    let options = SearchOptions {
+        whole_word: true,
+        ..Default::default()
+    };
+    assert_eq!(find(text, "cat", options), ["Cat", "cat", "CAT"]);
+
+    // Literal text should not be treated as regex
+    let options = SearchOptions::default();
+    assert_eq!(find("a.b axb", "a.b", options), ["a.b"]);
+
+    // Regex
+    let options = SearchOptions {
+        regex: true,
+        ..Default::default()
+    };
+    assert_eq!(find("a.b axb", "a.b", options), ["a.b", "axb"]);
+}
+
+#[test]
+fn invalid_or_empty_query() {
+    let options = SearchOptions {
+        regex: true,
+        ..Default::default()
+    };
+    assert!(Search::new("", options).is_none());
+    assert!(matches!(Search::new("(", options), Some(Err(_))));
+
+    // Same query is valid when literal
+    let options = SearchOptions::default();
+    assert!(matches!(Search::new("(", options), Some(Ok(_))));
+}
+
+#[test]
+fn replace() {
+    // Literal replacement should not expand capture groups
+    let search = Search::new("cat", SearchOptions::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        search.replace_all("cat Cat dog", "$0"),
+        (String::from("$0 $0 dog"), 2)
+    );
+
+    // Regex replacement should expand capture groups
+    let options = SearchOptions {
+        regex: true,
+        ..Default::default()
+    };
+    let search = Search::new(r"(\w+)@(\w+)", options).unwrap().unwrap();
+    assert_eq!(
+        search.replace_all("a@b c@d", "$2@$1"),
+        (String::from("b@a d@c"), 2)
+    );
+
+    // Replace single match
+    assert_eq!(
+        search.replace_at("a@b c@d", 4..7, "x"),
+        Some(String::from("a@b x"))
+    );
+    // Range is not a match
+    assert_eq!(search.replace_at("a@b c@d", 3..7, "x"), None);
+
+    // Empty matches are not replaced
+    let search = Search::new("x*", options).unwrap().unwrap();
+    assert_eq!(search.replace_all("axxb", "-"), (String::from("a-b"), 1));
+}
@@ -0,0 +1,168 @@
+use std::{fs, path::PathBuf};
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::{file::Cipher, get_config_dir, Keybinds};
+
+/// Name of file in config directory, which stores settings
+const SETTINGS_FILENAME: &str = "settings.toml";
+
+/// User preferences, persisted as TOML in config directory
+///
+/// Missing values use defaults, so old settings files can still be loaded
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Initial width of window, when program opens
+    pub window_width: f32,
+    /// Initial height of window, when program opens
+    pub window_height: f32,
+
+    /// Size of editor and interface text
+    pub font_size: f32,
+    /// Color theme of interface
+    pub theme: Theme,
+    /// Whether long lines are wrapped in editor
+    pub wrap: bool,
+
+    /// Whether registered files are saved automatically
+    pub autosave: bool,
+    /// Seconds since last edit before saving automatically
+    pub autosave_delay: u64,
+
+    /// Minutes of inactivity before editor is locked
+    ///
+    /// `0` to never lock
+    pub lock_timeout: u64,
+
+    /// Directory to open file dialogs in, if no file was opened recently
+    ///
+    /// `None` to use documents directory
+    pub default_dir: Option<String>,
+    /// Extension of encrypted files, without leading dot
+    pub file_extension: String,
+    /// Cipher of new files, which can be changed for each file
+    pub default_cipher: Cipher,
+    /// Whether list of recent files is encrypted, as paths may be sensitive
+    pub encrypt_recent_files: bool,
+
+    /// Keybind for each command
+    pub keybinds: Keybinds,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_width: 600.0,
+            window_height: 400.0,
+            font_size: 14.0,
+            theme: Theme::Dark,
+            wrap: true,
+            autosave: false,
+            autosave_delay: 5,
+            lock_timeout: 0,
+            default_dir: None,
+            file_extension: String::from("enc"),
+            default_cipher: Cipher::default(),
+            encrypt_recent_files: true,
+            keybinds: Keybinds::default(),
+        }
+    }
+}
+
+/// Color theme of interface
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Settings {
+    /// Load settings from config directory
+    ///
+    /// Returns default settings if file does not exist, or could not be read
+    pub fn load() -> Self {
+        let Some(path) = get_settings_path() else {
+            return Self::default();
+        };
+
+        let Ok(text) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&text) {
+            Ok(settings) => settings,
+
+            // Invalid file
+            // Use defaults, but do not overwrite file until settings are changed
+            Err(error) => {
+                eprintln!("Failed to parse settings: {}", error);
+                Self::default()
+            }
+        }
+    }
+
+    /// Save settings to config directory
+    ///
+    /// Errors are printed to stderr, but otherwise ignored
+    pub fn save(&self) {
+        let Some(path) = get_settings_path() else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            if let Err(error) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create config directory: {:?}", error);
+                return;
+            }
+        }
+
+        let text = match toml::to_string_pretty(self) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("Failed to serialize settings: {}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = fs::write(path, text) {
+            eprintln!("Failed to save settings: {:?}", error);
+        }
+    }
+
+    /// Get default directory as path, if it exists
+    pub fn default_dir(&self) -> Option<PathBuf> {
+        self.default_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_dir())
+    }
+
+    /// Create egui style from theme and font size
+    ///
+    /// Uses `base` for any values which are not configurable
+    pub fn style(&self, base: &egui::Style) -> egui::Style {
+        let mut style = base.clone();
+
+        style.visuals = match self.theme {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        };
+
+        for (text_style, font_id) in style.text_styles.iter_mut() {
+            font_id.size = match text_style {
+                egui::TextStyle::Heading => self.font_size * 1.4,
+                egui::TextStyle::Small => self.font_size * 0.7,
+                _ => self.font_size,
+            };
+        }
+
+        style
+    }
+}
+
+/// Get path of file which stores settings
+fn get_settings_path() -> Option<PathBuf> {
+    get_config_dir().map(|dir| dir.join(SETTINGS_FILENAME))
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests;
+
+/// Counts of characters, words, and lines in text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStats {
+    pub chars: usize,
+    pub words: usize,
+    /// Empty text has 1 line
+    pub lines: usize,
+    pub line_ending: LineEnding,
+}
+
+/// Style of line endings used in text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineEnding {
+    /// Text has only 1 line
+    None,
+    /// Unix style `\n`
+    Lf,
+    /// Windows style `\r\n`
+    CrLf,
+    /// Both styles are used
+    Mixed,
+}
+
+impl LineEnding {
+    /// Short name to display
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+            Self::Mixed => "Mixed",
+        }
+    }
+}
+
+impl TextStats {
+    /// Count characters, words, and lines in text
+    pub fn new(text: &str) -> Self {
+        let mut lf = 0;
+        let mut crlf = 0;
+        let mut previous = None;
+
+        for byte in text.bytes() {
+            if byte == b'\n' {
+                if previous == Some(b'\r') {
+                    crlf += 1;
+                } else {
+                    lf += 1;
+                }
+            }
+            previous = Some(byte);
+        }
+
+        let line_ending = match (lf > 0, crlf > 0) {
+            (false, false) => LineEnding::None,
+            (true, false) => LineEnding::Lf,
+            (false, true) => LineEnding::CrLf,
+            (true, true) => LineEnding::Mixed,
+        };
+
+        Self {
+            chars: text.chars().count(),
+            words: text.split_whitespace().count(),
+            lines: lf + crlf + 1,
+            line_ending,
+        }
+    }
+}
+
+/// Get line and column of character index, both starting at 1
+///
+/// Column is counted in characters, not bytes
+pub fn line_and_column(text: &str, char_index: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut column = 1;
+
+    for ch in text.chars().take(char_index) {
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+
+    (line, column)
+}
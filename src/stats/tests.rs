@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn count_text() {
+    let stats = TextStats::new("");
+    assert_eq!(stats.chars, 0);
+    assert_eq!(stats.words, 0);
+    assert_eq!(stats.lines, 1);
+    assert_eq!(stats.line_ending, LineEnding::None);
+
+    let stats = TextStats::new("héllo  world\nfoo\n");
+    assert_eq!(stats.chars, 17);
+    assert_eq!(stats.words, 3);
+    assert_eq!(stats.lines, 3);
+    assert_eq!(stats.line_ending, LineEnding::Lf);
+
+    let stats = TextStats::new("a\r\nb\r\n");
+    assert_eq!(stats.lines, 3);
+    assert_eq!(stats.line_ending, LineEnding::CrLf);
+
+    let stats = TextStats::new("a\r\nb\nc");
+    assert_eq!(stats.lines, 3);
+    assert_eq!(stats.line_ending, LineEnding::Mixed);
+}
+
+#[test]
+fn cursor_position() {
+    let text = "ab\nçd\n";
+
+    assert_eq!(line_and_column(text, 0), (1, 1));
+    assert_eq!(line_and_column(text, 2), (1, 3));
+    assert_eq!(line_and_column(text, 3), (2, 1));
+    // Multi-byte characters count as one column
+    assert_eq!(line_and_column(text, 5), (2, 3));
+    assert_eq!(line_and_column(text, 6), (3, 1));
+    // Past end of text
+    assert_eq!(line_and_column(text, 100), (3, 1));
+}
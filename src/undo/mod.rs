@@ -0,0 +1,290 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum edits to remember, oldest are forgotten first
+const MAX_EDITS: usize = 1000;
+
+/// Edits closer together than this may be combined into one edit
+const COALESCE_TIME: Duration = Duration::from_secs(1);
+
+/// Single change to text, which can be undone and redone
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Edit {
+    /// Byte index where text was changed
+    position: usize,
+    /// Text which was removed
+    removed: String,
+    /// Text which was inserted
+    inserted: String,
+}
+
+impl Edit {
+    /// Get edit which changes `old` text to `new` text
+    ///
+    /// Returns `None` if texts are the same
+    fn between(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+
+        // Length of same text at start of both
+        let prefix = old
+            .char_indices()
+            .zip(new.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(old.len().min(new.len()), |((index, _), _)| index);
+
+        // Length of same text at end of both, not overlapping prefix
+        let suffix: usize = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(ch, _)| ch.len_utf8())
+            .sum();
+
+        Some(Self {
+            position: prefix,
+            removed: old[prefix..old.len() - suffix].to_string(),
+            inserted: new[prefix..new.len() - suffix].to_string(),
+        })
+    }
+
+    /// Combine next edit into this edit, if it continues typing or deleting
+    ///
+    /// Returns `false` if edits cannot be combined
+    fn coalesce(&mut self, next: &Self) -> bool {
+        // Typing
+        if self.removed.is_empty() && next.removed.is_empty() {
+            // New lines start a new edit
+            if next.position == self.position + self.inserted.len()
+                && !self.inserted.ends_with('\n')
+            {
+                self.inserted += &next.inserted;
+                return true;
+            }
+        }
+
+        // Deleting
+        if self.inserted.is_empty() && next.inserted.is_empty() {
+            // Backspace
+            if next.position + next.removed.len() == self.position {
+                self.position = next.position;
+                self.removed.insert_str(0, &next.removed);
+                return true;
+            }
+            // Delete
+            if next.position == self.position {
+                self.removed += &next.removed;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Apply edit to text, if text matches
+    ///
+    /// Returns byte range of inserted text, or `None` if text does not match
+    fn apply(
+        text: &mut String,
+        position: usize,
+        removed: &str,
+        inserted: &str,
+    ) -> Option<Range<usize>> {
+        let range = position..position + removed.len();
+        if text.get(range.clone())? != removed {
+            return None;
+        }
+
+        text.replace_range(range, inserted);
+        Some(position..position + inserted.len())
+    }
+
+    /// Short description, for history list
+    pub fn describe(&self) -> String {
+        let action = match (self.removed.is_empty(), self.inserted.is_empty()) {
+            (true, _) => "Typed",
+            (false, true) => "Deleted",
+            (false, false) => "Replaced",
+        };
+        let text = if self.inserted.is_empty() {
+            &self.removed
+        } else {
+            &self.inserted
+        };
+
+        // Show start of text on one line
+        let mut preview: String = text.chars().take(24).collect();
+        if preview.len() < text.len() {
+            preview += "...";
+        }
+        format!("{} {:?}", action, preview)
+    }
+}
+
+/// Undo and redo history of text
+///
+/// Edits are recorded by comparing text with the text from the previous recording
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UndoHistory {
+    /// Every remembered edit, oldest first
+    edits: Vec<Edit>,
+    /// Number of edits which are applied to text
+    ///
+    /// Edits after this have been undone, and can be redone
+    applied: usize,
+
+    /// Text at last recording, to find next edit
+    #[serde(skip)]
+    text: String,
+    /// Time of last recording, to combine quick edits
+    #[serde(skip)]
+    last_record: Option<Instant>,
+}
+
+impl UndoHistory {
+    /// Create empty history, starting from text
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Continue history from text, such as after history is loaded from a file
+    ///
+    /// Clears history if text does not match the applied edits
+    pub fn resume(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self.last_record = None;
+
+        // Check every edit can be undone and redone, without changing text
+        let mut check = self.text.clone();
+        let valid = self.applied <= self.edits.len()
+            && (0..self.applied)
+                .rev()
+                .all(|index| self.undo_edit(&mut check, index).is_some())
+            && (0..self.edits.len()).all(|index| self.redo_edit(&mut check, index).is_some());
+
+        if valid {
+            self
+        } else {
+            eprintln!("Undo history does not match text, and was cleared");
+            Self::new(text)
+        }
+    }
+
+    /// Record change from previous text to new text
+    ///
+    /// Forgets any undone edits, which can no longer be redone
+    pub fn record(&mut self, text: &str) {
+        let Some(edit) = Edit::between(&self.text, text) else {
+            return;
+        };
+        self.text = text.to_string();
+
+        // Combine with previous edit, if it was recent and not undone
+        let recent = self
+            .last_record
+            .is_some_and(|time| time.elapsed() < COALESCE_TIME);
+        self.last_record = Some(Instant::now());
+
+        if recent && self.applied == self.edits.len() {
+            if let Some(last) = self.edits.last_mut() {
+                if last.coalesce(&edit) {
+                    return;
+                }
+            }
+        }
+
+        self.edits.truncate(self.applied);
+        self.edits.push(edit);
+
+        if self.edits.len() > MAX_EDITS {
+            self.edits.remove(0);
+        }
+        self.applied = self.edits.len();
+    }
+
+    /// Returns `true` if there is an edit to undo
+    pub fn can_undo(&self) -> bool {
+        self.applied > 0
+    }
+
+    /// Returns `true` if there is an undone edit to redo
+    pub fn can_redo(&self) -> bool {
+        self.applied < self.edits.len()
+    }
+
+    /// Undo last applied edit
+    ///
+    /// Returns byte range of restored text, to select in editor
+    pub fn undo(&mut self, text: &mut String) -> Option<Range<usize>> {
+        self.jump_to(self.applied.checked_sub(1)?, text)
+    }
+
+    /// Redo next undone edit
+    ///
+    /// Returns byte range of restored text, to select in editor
+    pub fn redo(&mut self, text: &mut String) -> Option<Range<usize>> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.jump_to(self.applied + 1, text)
+    }
+
+    /// Undo or redo edits, until the given number of edits are applied
+    ///
+    /// Returns byte range of text changed by the last edit, to select in editor
+    ///
+    /// Clears history if text does not match, such as if it was changed without being recorded
+    pub fn jump_to(&mut self, applied: usize, text: &mut String) -> Option<Range<usize>> {
+        let applied = applied.min(self.edits.len());
+        let mut range = None;
+
+        while self.applied != applied {
+            range = if self.applied > applied {
+                self.applied -= 1;
+                self.undo_edit(text, self.applied)
+            } else {
+                self.applied += 1;
+                self.redo_edit(text, self.applied - 1)
+            };
+
+            if range.is_none() {
+                eprintln!("Text does not match undo history, and history was cleared");
+                *self = Self::new(text);
+                return None;
+            }
+        }
+
+        self.text = text.clone();
+        self.last_record = None;
+        range
+    }
+
+    /// Get every edit, oldest first, and the number which are applied
+    pub fn edits(&self) -> (&[Edit], usize) {
+        (&self.edits, self.applied)
+    }
+
+    /// Reverse edit at index
+    fn undo_edit(&self, text: &mut String, index: usize) -> Option<Range<usize>> {
+        let edit = &self.edits[index];
+        Edit::apply(text, edit.position, &edit.inserted, &edit.removed)
+    }
+
+    /// Apply edit at index
+    fn redo_edit(&self, text: &mut String, index: usize) -> Option<Range<usize>> {
+        let edit = &self.edits[index];
+        Edit::apply(text, edit.position, &edit.removed, &edit.inserted)
+    }
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+#[test]
+fn edit_between_texts() {
+    assert_eq!(Edit::between("abc", "abc"), None);
+
+    let edit = Edit::between("héllo world", "héllo there world").unwrap();
+    assert_eq!(edit.position, "héllo ".len());
+    assert_eq!(edit.removed, "");
+    assert_eq!(edit.inserted, "there ");
+
+    // Repeated characters should not overlap
+    let edit = Edit::between("aaa", "aa").unwrap();
+    assert_eq!(edit.position, 2);
+    assert_eq!(edit.removed, "a");
+    assert_eq!(edit.inserted, "");
+
+    let edit = Edit::between("ab", "éb").unwrap();
+    assert_eq!(edit.position, 0);
+    assert_eq!(edit.removed, "a");
+    assert_eq!(edit.inserted, "é");
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut text = String::from("hello");
+    let mut history = UndoHistory::new(&text);
+
+    // Typing is combined into one edit
+    for ch in [" ", "w", "o"] {
+        text += ch;
+        history.record(&text);
+    }
+    // Text after a new line starts another edit
+    text += "\n";
+    history.record(&text);
+    text += "x";
+    history.record(&text);
+    assert_eq!(history.edits().0.len(), 2);
+
+    assert_eq!(history.undo(&mut text), Some(9..9));
+    assert_eq!(text, "hello wo\n");
+    assert_eq!(history.undo(&mut text), Some(5..5));
+    assert_eq!(text, "hello");
+    assert!(!history.can_undo());
+    assert_eq!(history.undo(&mut text), None);
+
+    assert_eq!(history.redo(&mut text), Some(5..9));
+    assert_eq!(text, "hello wo\n");
+
+    // New edit forgets undone edit
+    text.insert(0, '>');
+    history.record(&text);
+    assert!(!history.can_redo());
+    history.jump_to(0, &mut text);
+    assert_eq!(text, "hello");
+
+    // Persisted history must match text
+    history.jump_to(2, &mut text);
+    let resumed = history.clone().resume(&text);
+    assert_eq!(resumed.edits().0.len(), 2);
+    let resumed = history.resume("other text");
+    assert_eq!(resumed.edits().0.len(), 0);
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, Write};
+
+use chrono::{DateTime, Duration, Local, TimeZone};
+use flate2::{write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::file::{decompress, MAX_DECOMPRESSED_SIZE};
+
+/// Text of file at a previous save
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    /// Time of save, in seconds since Unix epoch
+    time: i64,
+    /// Text of file, compressed with deflate
+    #[serde(with = "serde_bytes")]
+    compressed: Vec<u8>,
+}
+
+impl Version {
+    /// Compress text
+    fn new(text: &str, time: i64) -> io::Result<Self> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes())?;
+
+        Ok(Self {
+            time,
+            compressed: encoder.finish()?,
+        })
+    }
+
+    /// Get time of save, in local timezone
+    pub fn time(&self) -> DateTime<Local> {
+        Local
+            .timestamp_opt(self.time, 0)
+            .single()
+            .unwrap_or_default()
+    }
+
+    /// Decompress text, up to `MAX_DECOMPRESSED_SIZE`
+    pub fn text(&self) -> io::Result<String> {
+        let bytes = decompress(&self.compressed, MAX_DECOMPRESSED_SIZE)?;
+        String::from_utf8(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// Text of file at previous saves, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct VersionHistory {
+    versions: Vec<Version>,
+}
+
+impl VersionHistory {
+    /// Add text as latest version, at current time
+    ///
+    /// Does nothing if text is the same as the latest version
+    pub fn add(&mut self, text: &str) -> io::Result<()> {
+        self.add_at(text, Local::now().timestamp())
+    }
+
+    /// Add text as latest version, at given time
+    fn add_at(&mut self, text: &str, time: i64) -> io::Result<()> {
+        if let Some(latest) = self.versions.last() {
+            if latest.text()? == text {
+                return Ok(());
+            }
+        }
+
+        self.versions.push(Version::new(text, time)?);
+        Ok(())
+    }
+
+    /// Get every version, oldest first
+    pub fn versions(&self) -> &[Version] {
+        &self.versions
+    }
+
+    /// Remove version at index
+    pub fn remove(&mut self, index: usize) {
+        if index < self.versions.len() {
+            self.versions.remove(index);
+        }
+    }
+
+    /// Remove all but the latest versions
+    ///
+    /// Returns number of versions removed
+    pub fn keep_latest(&mut self, count: usize) -> usize {
+        let removed = self.versions.len().saturating_sub(count);
+        self.versions.drain(..removed);
+        removed
+    }
+
+    /// Remove versions which were saved longer ago than the given age
+    ///
+    /// Returns number of versions removed
+    pub fn remove_older_than(&mut self, age: Duration) -> usize {
+        let oldest = (Local::now() - age).timestamp();
+        let count = self.versions.len();
+        self.versions.retain(|version| version.time >= oldest);
+        count - self.versions.len()
+    }
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+#[test]
+fn add_and_prune_versions() {
+    let mut history = VersionHistory::default();
+    let now = Local::now().timestamp();
+    let day = 24 * 60 * 60;
+
+    history.add_at("first", now - 10 * day).unwrap();
+    history.add_at("second", now - 2 * day).unwrap();
+    // Same as latest version
+    history.add_at("second", now - day).unwrap();
+    history.add_at("third", now).unwrap();
+
+    let texts: Vec<_> = history
+        .versions()
+        .iter()
+        .map(|version| version.text().unwrap())
+        .collect();
+    assert_eq!(texts, ["first", "second", "third"]);
+
+    assert_eq!(history.remove_older_than(Duration::days(5)), 1);
+    assert_eq!(history.versions().len(), 2);
+
+    assert_eq!(history.keep_latest(5), 0);
+    assert_eq!(history.keep_latest(1), 1);
+    assert_eq!(history.versions()[0].text().unwrap(), "third");
+}